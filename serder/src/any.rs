@@ -0,0 +1,202 @@
+use crate::{
+    read_u8, split_tlv, tlv_len, write_header, DerDeserialize, DerError, DerSerialize, Tag,
+};
+use std::io::{self, Write};
+
+/// Any single TLV, captured as its complete encoding without interpreting the
+/// content. This is ASN.1 `ANY`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnyTlv {
+    bytes: Vec<u8>,
+    header_len: usize,
+}
+
+impl AnyTlv {
+    /// Builds a TLV with a single byte identifier and the given content.
+    pub fn new(tag: u8, contents: &[u8]) -> Self {
+        let mut bytes = Vec::with_capacity(crate::tlv_len(contents.len()));
+        crate::write_header(tag, contents.len(), &mut bytes).expect("writing to a Vec never fails");
+        let header_len = bytes.len();
+        bytes.extend_from_slice(contents);
+
+        AnyTlv { bytes, header_len }
+    }
+
+    /// The first identifier octet.
+    pub fn tag(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    pub fn contents(&self) -> &[u8] {
+        &self.bytes[self.header_len..]
+    }
+
+    /// The complete encoding, identifier and length included.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Takes the complete encoding, identifier and length included.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Decodes the captured TLV as a `T`.
+    pub fn parse<T: DerDeserialize>(&self) -> Result<T, DerError> {
+        let mut bytes = &self.bytes[..];
+        let value = T::deserialize(&mut bytes)?;
+
+        if !bytes.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(value)
+    }
+}
+
+impl DerSerialize for AnyTlv {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        writer.write_all(&self.bytes)?;
+
+        Ok(self.bytes.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl DerDeserialize for AnyTlv {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        let (tlv, header_len) = split_tlv(bytes)?;
+
+        Ok(AnyTlv {
+            bytes: tlv.to_vec(),
+            header_len,
+        })
+    }
+
+    fn matches_tag(_: u8) -> bool {
+        true
+    }
+}
+
+/// Opaque content under an identifier chosen by the caller, for components
+/// like CMS's `[0] IMPLICIT ANY`. Unlike `AnyTlv`, the tag can be replaced
+/// without touching the content. Only single-octet identifiers are
+/// supported.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TaggedAny {
+    pub tag: Tag,
+    pub content: Vec<u8>,
+}
+
+impl TaggedAny {
+    pub fn new(tag: Tag, content: impl Into<Vec<u8>>) -> Self {
+        TaggedAny {
+            tag,
+            content: content.into(),
+        }
+    }
+}
+
+impl DerSerialize for TaggedAny {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let written = write_header(self.tag.into_tag_value(), self.content.len(), writer)?;
+        writer.write_all(&self.content)?;
+
+        Ok(written + self.content.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.content.len())
+    }
+}
+
+impl DerDeserialize for TaggedAny {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        let mut rest = *bytes;
+        let (tlv, header_len) = split_tlv(&mut rest)?;
+        let tag = read_u8(&mut &tlv[..])?;
+
+        if tag & 0b0001_1111 == 0b0001_1111 {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        *bytes = rest;
+
+        Ok(TaggedAny {
+            tag: Tag(tag),
+            content: tlv[header_len..].to_vec(),
+        })
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag & 0b0001_1111 != 0b0001_1111
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_tlv_capture() {
+        let buffer = [0x04, 0x02, 0xAB, 0xCD, 0x05, 0x00];
+        let mut bytes = &buffer[..];
+
+        let any = AnyTlv::deserialize(&mut bytes).unwrap();
+        assert_eq!(any.tag(), 0x04);
+        assert_eq!(any.contents(), [0xAB, 0xCD]);
+        assert_eq!(any.to_der_vec(), &buffer[..4]);
+        assert_eq!(any, AnyTlv::new(0x04, &[0xAB, 0xCD]));
+
+        let any = AnyTlv::deserialize(&mut bytes).unwrap();
+        assert_eq!(any.tag(), 0x05);
+        assert!(any.contents().is_empty());
+        assert!(bytes.is_empty());
+        assert_eq!(any.into_bytes(), &buffer[4..]);
+    }
+
+    #[test]
+    fn any_tlv_parse() {
+        let buffer = 70_000u32.to_der_vec();
+        let any = AnyTlv::deserialize(&mut &buffer[..]).unwrap();
+
+        assert_eq!(any.parse::<u32>().unwrap(), 70_000);
+        assert!(matches!(any.parse::<u8>(), Err(DerError::IntValueTooLarge)));
+        assert!(matches!(
+            any.parse::<crate::OctetString>(),
+            Err(DerError::UnexpectedTag { .. })
+        ));
+    }
+
+    #[test]
+    fn tagged_any() {
+        // [0] IMPLICIT around the content of a SEQUENCE { INTEGER 5 }
+        let inner = AnyTlv::new(crate::SEQUENCE, &[0x02, 0x01, 0x05]);
+        let tagged = TaggedAny::new(
+            Tag::new(0).context_specific().constructed(),
+            inner.contents(),
+        );
+        let buffer = tagged.to_der_vec();
+        assert_eq!(buffer, [0xA0, 0x03, 0x02, 0x01, 0x05]);
+        assert_eq!(tagged.encoded_len(), buffer.len());
+        assert_eq!(TaggedAny::deserialize(&mut &buffer[..]).unwrap(), tagged);
+        assert_eq!(
+            crate::read_explicit(&mut &buffer[..], 0).unwrap(),
+            [0x02, 0x01, 0x05]
+        );
+
+        // Re-tagging keeps the content
+        let mut retagged = tagged.clone();
+        retagged.tag = Tag::new(crate::SEQUENCE);
+        assert_eq!(retagged.to_der_vec(), inner.as_bytes());
+
+        let primitive = TaggedAny::new(Tag::new(1).context_specific(), vec![0xAB, 0xCD]);
+        assert_eq!(primitive.to_der_vec(), [0x81, 0x02, 0xAB, 0xCD]);
+
+        // High tag numbers don't fit in a `Tag`
+        assert!(TaggedAny::deserialize(&mut &[0x9F, 0x1F, 0x00][..]).is_err());
+    }
+}
@@ -0,0 +1,685 @@
+//! Encoding and decoding with the BER indefinite length form, where a
+//! constructed value is terminated by an end-of-contents (EOC) marker instead
+//! of being prefixed by its length. This isn't valid DER.
+
+use crate::{
+    content_length, minimal_integer, read_contents, read_tag_number, read_u8, tlv_len,
+    DerDeserialize, DerError, DerSerialize, MAX_DEPTH, SEQUENCE,
+};
+use std::io::{self, Write};
+
+/// The length octet of the indefinite form.
+pub const INDEFINITE_LENGTH: u8 = 0x80;
+
+/// Writes the end-of-contents marker, `00 00`.
+pub fn write_eoc<W: Write + ?Sized>(writer: &mut W) -> io::Result<usize> {
+    writer.write_all(&[0x00, 0x00])?;
+
+    Ok(2)
+}
+
+/// Whether `bytes` begins with the end-of-contents marker.
+pub fn is_eoc(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0x00, 0x00])
+}
+
+/// Reads a BER length, returning `None` for the indefinite form. Unlike DER,
+/// long form lengths don't need to be minimal. The reserved initial octet
+/// `0xFF` is `DerError::InvalidEncoding`.
+pub fn read_length(bytes: &mut &[u8]) -> Result<Option<usize>, DerError> {
+    let first_byte = read_u8(bytes)?;
+
+    match first_byte {
+        0..=0x7F => Ok(Some(usize::from(first_byte))),
+        INDEFINITE_LENGTH => Ok(None),
+        0xFF => Err(DerError::InvalidEncoding),
+        _ => {
+            let mut length = 0usize;
+
+            for _ in 0..first_byte & 0b0111_1111 {
+                length = length.checked_mul(256).ok_or(DerError::IntValueTooLarge)?
+                    | usize::from(read_u8(bytes)?);
+            }
+
+            Ok(Some(length))
+        }
+    }
+}
+
+/// Writes a constructed value with the indefinite length form: `tag`, the
+/// elements written by `body`, then the EOC marker.
+pub fn write_indefinite<W, F>(tag: u8, writer: &mut W, body: F) -> io::Result<usize>
+where
+    W: Write + ?Sized,
+    F: FnOnce(&mut W) -> io::Result<usize>,
+{
+    writer.write_all(&[tag])?;
+    writer.write_all(&[INDEFINITE_LENGTH])?;
+    let written = body(writer)?;
+
+    Ok(2 + written + write_eoc(writer)?)
+}
+
+/// Writes `elements` as a SEQUENCE OF with the indefinite length form.
+pub fn serialize_sequence_of<T: DerSerialize, W: Write + ?Sized>(
+    elements: &[T],
+    writer: &mut W,
+) -> io::Result<usize> {
+    write_indefinite(SEQUENCE, writer, |writer| {
+        elements.iter().try_fold(0, |written, element| {
+            Ok(written + element.serialize(writer)?)
+        })
+    })
+}
+
+/// Reads a SEQUENCE OF with either the definite or indefinite length form.
+/// An indefinite length sequence ends at the first EOC marker.
+pub fn deserialize_sequence_of<T: DerDeserialize>(bytes: &mut &[u8]) -> Result<Vec<T>, DerError> {
+    if !bytes.starts_with(&[SEQUENCE, INDEFINITE_LENGTH]) {
+        let mut contents = read_contents(bytes, SEQUENCE)?;
+        let mut elements = Vec::new();
+
+        while !contents.is_empty() {
+            elements.push(T::deserialize(&mut contents)?);
+        }
+
+        return Ok(elements);
+    }
+
+    *bytes = &bytes[2..];
+    let mut elements = Vec::new();
+
+    while !is_eoc(bytes) {
+        if bytes.is_empty() {
+            return Err(DerError::UnexpectedEof);
+        }
+
+        elements.push(T::deserialize(bytes)?);
+    }
+
+    *bytes = &bytes[2..];
+
+    Ok(elements)
+}
+
+/// Re-encodes a single BER element as DER, without a schema:
+///
+/// * indefinite and non-minimal lengths become minimal definite lengths
+/// * constructed OCTET STRING, BIT STRING and character strings are joined
+///   into one primitive value
+/// * BOOLEAN TRUE becomes `0xFF`, INTEGER and ENUMERATED lose redundant sign
+///   bytes, and BIT STRING unused bits are cleared
+/// * SET components are sorted by tag, then by encoding
+pub fn der_canonicalize(ber_bytes: &[u8]) -> Result<Vec<u8>, DerError> {
+    let mut bytes = ber_bytes;
+    let mut der = Vec::with_capacity(ber_bytes.len());
+    canonicalize(&mut bytes, &mut der, 0)?;
+
+    if !bytes.is_empty() {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    Ok(der)
+}
+
+/// Checks that `bytes` is a single element already in DER form, so that
+/// `der_canonicalize` would return it unchanged.
+pub fn verify_der(bytes: &[u8]) -> Result<(), DerError> {
+    if der_canonicalize(bytes)? == bytes {
+        Ok(())
+    } else {
+        Err(DerError::InvalidEncoding)
+    }
+}
+
+/// The strictest set of encoding rules a blob conforms to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EncodingRules {
+    /// Distinguished: minimal definite lengths everywhere
+    Der,
+    /// Canonical: the indefinite form for every constructed value, with
+    /// strings over 1000 octets split into 1000 octet segments
+    Cer,
+    /// Well formed, but not canonical under either
+    Ber,
+}
+
+/// Walks a single BER element, checking for indefinite lengths, non-minimal
+/// lengths and integers, unsorted SETs and the other forms
+/// `der_canonicalize` rewrites, to find which rules it was encoded with.
+pub fn detect_rules(bytes: &[u8]) -> Result<EncodingRules, DerError> {
+    let mut rest = bytes;
+    let mut findings = Findings::default();
+    scan(&mut rest, &mut findings, 0)?;
+
+    if !rest.is_empty() {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    Ok(match findings {
+        Findings { not_der: false, .. } => EncodingRules::Der,
+        Findings { not_cer: false, .. } => EncodingRules::Cer,
+        _ => EncodingRules::Ber,
+    })
+}
+
+// CER's maximum primitive string, and segment, size
+const CER_SEGMENT_LEN: usize = 1000;
+
+#[derive(Default)]
+struct Findings {
+    not_der: bool,
+    not_cer: bool,
+}
+
+impl Findings {
+    fn non_canonical(&mut self) {
+        self.not_der = true;
+        self.not_cer = true;
+    }
+}
+
+// Checks the next element, returning its encoding. `depth` counts the
+// constructed values it's inside of.
+fn scan<'a>(
+    bytes: &mut &'a [u8],
+    findings: &mut Findings,
+    depth: usize,
+) -> Result<&'a [u8], DerError> {
+    let start = *bytes;
+    let (class_and_form, number) = read_tag_number(bytes)?;
+    let identifier = start[0];
+    let universal = class_and_form & 0b1100_0000 == 0;
+
+    // Universal tag 0 is reserved for the EOC marker, which is only allowed
+    // to end indefinite length contents and is consumed by the caller there
+    if universal && number == 0 {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    let length_start = *bytes;
+    let length = read_length(bytes)?;
+    let length_len = length_start.len() - bytes.len();
+
+    if let Some(length) = length {
+        if length_len != tlv_len(length) - length - 1 {
+            findings.non_canonical();
+        }
+    }
+
+    if class_and_form & CONSTRUCTED == 0 {
+        let contents = take(bytes, length.ok_or(DerError::InvalidEncoding)?)?;
+
+        if universal {
+            if canonical_primitive(number, contents)? != contents {
+                findings.non_canonical();
+            }
+
+            if is_string_type(number) && contents.len() > CER_SEGMENT_LEN {
+                findings.not_cer = true;
+            }
+        }
+
+        return Ok(&start[..start.len() - bytes.len()]);
+    }
+
+    if depth == MAX_DEPTH {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    let mut elements = Vec::new();
+
+    match length {
+        Some(length) => {
+            findings.not_cer = true;
+            let mut contents = take(bytes, length)?;
+
+            while !contents.is_empty() {
+                elements.push(scan(&mut contents, findings, depth + 1)?);
+            }
+        }
+        None => {
+            findings.not_der = true;
+
+            while !is_eoc(bytes) {
+                elements.push(scan(bytes, findings, depth + 1)?);
+            }
+
+            *bytes = &bytes[2..];
+        }
+    }
+
+    if universal && is_string_type(number) {
+        findings.not_der = true;
+
+        if !is_cer_segmentation(identifier & !CONSTRUCTED, &elements) {
+            findings.not_cer = true;
+        }
+    }
+
+    if universal && number == 17 {
+        let sorted = elements.windows(2).all(|pair| {
+            ((pair[0][0] & !CONSTRUCTED), pair[0]) <= ((pair[1][0] & !CONSTRUCTED), pair[1])
+        });
+
+        if !sorted {
+            findings.non_canonical();
+        }
+    }
+
+    Ok(&start[..start.len() - bytes.len()])
+}
+
+// Whether a constructed string's segments are primitives of the same type,
+// each 1000 octets except a shorter last one, totalling more than 1000
+fn is_cer_segmentation(tag: u8, segments: &[&[u8]]) -> bool {
+    let mut lengths = Vec::with_capacity(segments.len());
+
+    for segment in segments {
+        match read_contents(&mut &segment[..], tag) {
+            Ok(contents) => lengths.push(contents.len()),
+            Err(_) => return false,
+        }
+    }
+
+    match lengths.split_last() {
+        Some((last, full)) => {
+            !full.is_empty()
+                && full.iter().all(|&len| len == CER_SEGMENT_LEN)
+                && (1..=CER_SEGMENT_LEN).contains(last)
+        }
+        None => false,
+    }
+}
+
+const CONSTRUCTED: u8 = 0b0010_0000;
+
+// Universal types that BER allows to be split into constructed segments
+fn is_string_type(number: u64) -> bool {
+    matches!(number, 3 | 4 | 12 | 18..=22 | 25..=30)
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], DerError> {
+    if len > bytes.len() {
+        return Err(DerError::UnexpectedEof);
+    }
+
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+
+    Ok(taken)
+}
+
+// Re-encodes the next element, appending it to `der`. `depth` counts the
+// constructed values it's inside of.
+fn canonicalize(bytes: &mut &[u8], der: &mut Vec<u8>, depth: usize) -> Result<(), DerError> {
+    let start = *bytes;
+    let (class_and_form, number) = read_tag_number(bytes)?;
+    let mut identifier = start[..start.len() - bytes.len()].to_vec();
+    let universal = class_and_form & 0b1100_0000 == 0;
+
+    // Universal tag 0 is only valid as the EOC marker, see `scan`
+    if universal && number == 0 {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    let length = read_length(bytes)?;
+
+    if class_and_form & CONSTRUCTED == 0 {
+        let contents = take(bytes, length.ok_or(DerError::InvalidEncoding)?)?;
+        let contents = if universal {
+            canonical_primitive(number, contents)?
+        } else {
+            contents.to_vec()
+        };
+
+        return write_element(&identifier, &contents, der);
+    }
+
+    if depth == MAX_DEPTH {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    let mut elements = Vec::new();
+
+    match length {
+        Some(length) => {
+            let mut contents = take(bytes, length)?;
+
+            while !contents.is_empty() {
+                let mut element = Vec::new();
+                canonicalize(&mut contents, &mut element, depth + 1)?;
+                elements.push(element);
+            }
+        }
+        None => {
+            while !is_eoc(bytes) {
+                let mut element = Vec::new();
+                canonicalize(bytes, &mut element, depth + 1)?;
+                elements.push(element);
+            }
+
+            *bytes = &bytes[2..];
+        }
+    }
+
+    if universal && is_string_type(number) {
+        // Segments were canonicalized to primitives of the same type already
+        identifier[0] &= !CONSTRUCTED;
+        let contents = join_segments(identifier[0], number, &elements)?;
+
+        return write_element(&identifier, &contents, der);
+    }
+
+    if universal && number == 17 {
+        elements.sort_by(|a, b| {
+            (a[0] & !CONSTRUCTED)
+                .cmp(&(b[0] & !CONSTRUCTED))
+                .then_with(|| a.cmp(b))
+        });
+    }
+
+    write_element(&identifier, &elements.concat(), der)
+}
+
+fn canonical_primitive(number: u64, contents: &[u8]) -> Result<Vec<u8>, DerError> {
+    match (number, contents) {
+        // BOOLEAN
+        (1, [value]) => Ok(vec![if *value == 0 { 0x00 } else { 0xFF }]),
+        (1, _) => Err(DerError::InvalidEncoding),
+        // INTEGER and ENUMERATED
+        (2, []) | (10, []) => Err(DerError::InvalidEncoding),
+        (2, _) | (10, _) => Ok(minimal_integer(contents).to_vec()),
+        // BIT STRING
+        (3, [unused, bits @ ..]) if *unused < 8 && (*unused == 0 || !bits.is_empty()) => {
+            let mut contents = contents.to_vec();
+
+            if let Some(last) = contents.last_mut().filter(|_| !bits.is_empty()) {
+                *last &= 0xFF << unused;
+            }
+
+            Ok(contents)
+        }
+        (3, _) => Err(DerError::InvalidEncoding),
+        _ => Ok(contents.to_vec()),
+    }
+}
+
+// Joins the DER segments of a constructed string into primitive contents
+fn join_segments(tag: u8, number: u64, segments: &[Vec<u8>]) -> Result<Vec<u8>, DerError> {
+    let mut contents = Vec::new();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let mut segment = &segment[..];
+
+        let segment_contents = read_contents(&mut segment, tag)?;
+
+        if number != 3 {
+            contents.extend_from_slice(segment_contents);
+            continue;
+        }
+
+        // Only the last BIT STRING segment may have unused bits
+        let (&unused, bits) = segment_contents
+            .split_first()
+            .ok_or(DerError::InvalidEncoding)?;
+
+        if i == 0 {
+            contents.push(0);
+        }
+
+        if unused != 0 && i != segments.len() - 1 {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        contents[0] = unused;
+        contents.extend_from_slice(bits);
+    }
+
+    if number == 3 && contents.is_empty() {
+        contents.push(0);
+    }
+
+    Ok(contents)
+}
+
+fn write_element(identifier: &[u8], contents: &[u8], der: &mut Vec<u8>) -> Result<(), DerError> {
+    der.extend_from_slice(identifier);
+    content_length(contents.len())?.serialize(der)?;
+    der.extend_from_slice(contents);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eoc() {
+        let mut buffer = vec![];
+        assert_eq!(write_eoc(&mut buffer).unwrap(), 2);
+        assert_eq!(buffer, [0x00, 0x00]);
+        assert!(is_eoc(&buffer));
+        assert!(!is_eoc(&[0x00]));
+        assert!(!is_eoc(&[0x05, 0x00]));
+    }
+
+    #[test]
+    fn ber_lengths() {
+        assert_eq!(read_length(&mut &[0x05][..]).unwrap(), Some(5));
+        assert_eq!(read_length(&mut &[0x80][..]).unwrap(), None);
+        // Non-minimal long form is fine in BER
+        assert_eq!(read_length(&mut &[0x82, 0x00, 0x05][..]).unwrap(), Some(5));
+        assert!(read_length(&mut &[0x82, 0x01][..]).is_err());
+
+        // The reserved initial octet, even with enough octets after it
+        let mut reserved = vec![0xFF];
+        reserved.resize(128, 0x00);
+        assert!(matches!(
+            read_length(&mut &reserved[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            der_canonicalize(&[0x04, 0xFF, 0x00]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn indefinite_sequence_of() {
+        let mut buffer = vec![];
+        let written = serialize_sequence_of(&[1u8, 2, 3], &mut buffer).unwrap();
+        assert_eq!(written, buffer.len());
+        assert_eq!(
+            buffer,
+            [0x30, 0x80, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02, 0x02, 0x01, 0x03, 0x00, 0x00]
+        );
+
+        // The decoder stops on the EOC, leaving what follows
+        buffer.extend_from_slice(&[0x05, 0x00]);
+        let mut bytes = &buffer[..];
+        assert_eq!(
+            deserialize_sequence_of::<u8>(&mut bytes).unwrap(),
+            [1, 2, 3]
+        );
+        assert_eq!(bytes, [0x05, 0x00]);
+
+        // Nested indefinite sequences
+        let mut buffer = vec![];
+        write_indefinite(SEQUENCE, &mut buffer, |writer| {
+            Ok(serialize_sequence_of(&[7u8], &mut *writer)?
+                + serialize_sequence_of::<u8, _>(&[], &mut *writer)?)
+        })
+        .unwrap();
+        // The elements are decoded as DER, which has no indefinite form
+        assert!(matches!(
+            deserialize_sequence_of::<Vec<u8>>(&mut &buffer[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+
+        // Definite lengths are accepted too
+        let buffer = vec![4u8, 5].to_der_vec();
+        assert_eq!(
+            deserialize_sequence_of::<u8>(&mut &buffer[..]).unwrap(),
+            [4, 5]
+        );
+
+        // Missing EOC
+        let buffer = [0x30, 0x80, 0x02, 0x01, 0x01];
+        assert!(matches!(
+            deserialize_sequence_of::<u8>(&mut &buffer[..]),
+            Err(DerError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn canonicalize_indefinite_sequence() {
+        let ber = [
+            0x30, 0x80, 0x02, 0x01, 0x05, 0x30, 0x80, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(
+            der_canonicalize(&ber).unwrap(),
+            [0x30, 0x07, 0x02, 0x01, 0x05, 0x30, 0x02, 0x05, 0x00]
+        );
+
+        // DER input is left as it is
+        let der = vec![1u32, 70_000].to_der_vec();
+        assert_eq!(der_canonicalize(&der).unwrap(), der);
+    }
+
+    #[test]
+    fn canonicalize_primitives() {
+        let ber = [
+            0x30, 0x81, 0x15, 0x02, 0x02, 0x00, 0x05, 0x01, 0x01, 0x01, 0x03, 0x02, 0x04, 0xFF,
+            0x24, 0x80, 0x04, 0x01, 0xAA, 0x04, 0x01, 0xBB, 0x00, 0x00,
+        ];
+        assert_eq!(
+            der_canonicalize(&ber).unwrap(),
+            [
+                0x30, 0x0E, 0x02, 0x01, 0x05, 0x01, 0x01, 0xFF, 0x03, 0x02, 0x04, 0xF0, 0x04, 0x02,
+                0xAA, 0xBB
+            ]
+        );
+
+        // SET components by tag, then SET OF elements by encoding
+        let ber = [
+            0x31, 0x0D, 0x30, 0x00, 0x02, 0x01, 0x02, 0x31, 0x06, 0x02, 0x01, 0x02, 0x02, 0x01,
+            0x01,
+        ];
+        assert_eq!(
+            der_canonicalize(&ber).unwrap(),
+            [
+                0x31, 0x0D, 0x02, 0x01, 0x02, 0x30, 0x00, 0x31, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01,
+                0x02
+            ]
+        );
+
+        // Constructed BIT STRING with unused bits before the last segment
+        let ber = [
+            0x23, 0x80, 0x03, 0x02, 0x01, 0xFE, 0x03, 0x02, 0x00, 0xFF, 0x00, 0x00,
+        ];
+        assert!(der_canonicalize(&ber).is_err());
+
+        // Indefinite primitive, trailing data
+        assert!(der_canonicalize(&[0x04, 0x80, 0x00, 0x00]).is_err());
+        assert!(der_canonicalize(&[0x05, 0x00, 0x05, 0x00]).is_err());
+    }
+
+    #[test]
+    fn eoc_outside_indefinite_contents() {
+        // An EOC inside definite contents, on its own, and with a length
+        for ber in [
+            &[0x30, 0x02, 0x00, 0x00][..],
+            &[0x30, 0x80, 0x30, 0x02, 0x00, 0x00, 0x00, 0x00][..],
+            &[0x00, 0x00][..],
+            &[0x30, 0x80, 0x00, 0x01, 0xAA, 0x00, 0x00][..],
+            &[0x20, 0x00][..],
+        ] {
+            assert!(matches!(
+                der_canonicalize(ber),
+                Err(DerError::InvalidEncoding)
+            ));
+            assert!(matches!(verify_der(ber), Err(DerError::InvalidEncoding)));
+            assert!(matches!(detect_rules(ber), Err(DerError::InvalidEncoding)));
+        }
+    }
+
+    // `depth` SEQUENCEs with the indefinite form, one inside the next
+    fn nested_indefinite(depth: usize) -> Vec<u8> {
+        let mut ber = [SEQUENCE, INDEFINITE_LENGTH].repeat(depth);
+        ber.resize(depth * 4, 0x00);
+
+        ber
+    }
+
+    #[test]
+    fn canonicalize_nesting_limit() {
+        let ber = nested_indefinite(MAX_DEPTH);
+        let der = der_canonicalize(&ber).unwrap();
+        assert!(verify_der(&der).is_ok());
+
+        for depth in [MAX_DEPTH + 1, 500_000] {
+            let ber = nested_indefinite(depth);
+            assert!(matches!(
+                der_canonicalize(&ber),
+                Err(DerError::InvalidEncoding)
+            ));
+            assert!(matches!(verify_der(&ber), Err(DerError::InvalidEncoding)));
+        }
+    }
+
+    #[test]
+    fn detect_encoding_rules() {
+        let der = vec![1u32, 70_000].to_der_vec();
+        assert_eq!(detect_rules(&der).unwrap(), EncodingRules::Der);
+        assert_eq!(detect_rules(&[0x05, 0x00]).unwrap(), EncodingRules::Der);
+
+        let indefinite = [
+            0x30, 0x80, 0x02, 0x01, 0x05, 0x30, 0x80, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(detect_rules(&indefinite).unwrap(), EncodingRules::Cer);
+
+        // Indefinite outside, definite inside
+        let mixed = [0x30, 0x80, 0x30, 0x02, 0x05, 0x00, 0x00, 0x00];
+        assert_eq!(detect_rules(&mixed).unwrap(), EncodingRules::Ber);
+
+        // Non-minimal INTEGER, non-minimal length, BOOLEAN TRUE that isn't 0xFF
+        for ber in [
+            &[0x02, 0x02, 0x00, 0x05][..],
+            &[0x04, 0x81, 0x01, 0xAA][..],
+            &[0x30, 0x80, 0x01, 0x01, 0x01, 0x00, 0x00][..],
+        ] {
+            assert_eq!(detect_rules(ber).unwrap(), EncodingRules::Ber);
+        }
+
+        // An unsorted SET OF
+        let unsorted = [0x31, 0x06, 0x02, 0x01, 0x02, 0x02, 0x01, 0x01];
+        assert_eq!(detect_rules(&unsorted).unwrap(), EncodingRules::Ber);
+
+        // A 1500 octet OCTET STRING in CER segments, and in short ones
+        let mut cer = vec![0x24, 0x80, 0x04, 0x82, 0x03, 0xE8];
+        cer.extend_from_slice(&[0xAA; 1000]);
+        cer.extend_from_slice(&[0x04, 0x82, 0x01, 0xF4]);
+        cer.extend_from_slice(&[0xBB; 500]);
+        cer.extend_from_slice(&[0x00, 0x00]);
+        assert_eq!(detect_rules(&cer).unwrap(), EncodingRules::Cer);
+
+        let segmented = [0x24, 0x80, 0x04, 0x01, 0xAA, 0x04, 0x01, 0xBB, 0x00, 0x00];
+        assert_eq!(detect_rules(&segmented).unwrap(), EncodingRules::Ber);
+
+        assert!(detect_rules(&[0x30, 0x80, 0x05, 0x00]).is_err());
+        assert!(detect_rules(&[0x05, 0x00, 0x05, 0x00]).is_err());
+    }
+
+    #[test]
+    fn detect_rules_nesting_limit() {
+        let ber = nested_indefinite(MAX_DEPTH);
+        assert_eq!(detect_rules(&ber).unwrap(), EncodingRules::Cer);
+
+        for depth in [MAX_DEPTH + 1, 500_000] {
+            let ber = nested_indefinite(depth);
+            assert!(matches!(detect_rules(&ber), Err(DerError::InvalidEncoding)));
+        }
+    }
+}
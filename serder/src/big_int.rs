@@ -0,0 +1,286 @@
+use crate::{
+    minimal_integer, read_contents, read_integer, tlv_len, write_header, DerDeserialize, DerError,
+    DerSerialize, DerSerializeContent, INTEGER,
+};
+use std::io::{self, Write};
+
+// Two's complement negation in place, wrapping on overflow
+fn negate(bytes: &mut [u8]) {
+    let mut carry = true;
+
+    for byte in bytes.iter_mut().rev() {
+        let (value, overflow) = (!*byte).overflowing_add(u8::from(carry));
+        *byte = value;
+        carry = overflow;
+    }
+}
+
+/// An INTEGER of any size, kept as its minimal big-endian two's complement
+/// encoding.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BigInt(Vec<u8>);
+
+impl BigInt {
+    /// Reads big-endian two's complement bytes. An empty slice is zero.
+    pub fn from_signed_bytes_be(bytes: &[u8]) -> Self {
+        match minimal_integer(bytes) {
+            [] => BigInt(vec![0]),
+            bytes => BigInt(bytes.to_vec()),
+        }
+    }
+
+    /// Reads a big-endian magnitude, such as a key or signature component.
+    pub fn from_unsigned_bytes_be(bytes: &[u8]) -> Self {
+        let mut padded = Vec::with_capacity(bytes.len() + 1);
+        padded.push(0);
+        padded.extend_from_slice(bytes);
+
+        BigInt::from_signed_bytes_be(&padded)
+    }
+
+    /// Builds `-magnitude` if `negative`, else `magnitude`, from a big-endian
+    /// absolute value.
+    pub fn from_magnitude(magnitude: &[u8], negative: bool) -> Self {
+        let mut bytes = Vec::with_capacity(magnitude.len() + 1);
+        bytes.push(0);
+        bytes.extend_from_slice(magnitude);
+
+        if negative {
+            negate(&mut bytes);
+        }
+
+        BigInt::from_signed_bytes_be(&bytes)
+    }
+
+    /// The big-endian absolute value, without any sign pad. Zero is a single
+    /// `0x00`.
+    pub fn magnitude_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.0.clone();
+
+        if self.is_negative() {
+            negate(&mut bytes);
+        }
+
+        let leading_zeros = bytes.iter().take_while(|&&b| b == 0).count();
+        bytes.drain(..leading_zeros.min(bytes.len() - 1));
+
+        bytes
+    }
+
+    /// The INTEGER content bytes.
+    pub fn as_signed_bytes_be(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The big-endian magnitude without the sign byte, or `None` if the value
+    /// is negative. Zero is a single `0x00`.
+    pub fn to_unsigned_bytes_be(&self) -> Option<&[u8]> {
+        match &self.0[..] {
+            _ if self.is_negative() => None,
+            [0, rest @ ..] if !rest.is_empty() => Some(rest),
+            bytes => Some(bytes),
+        }
+    }
+
+    /// The magnitude left-padded with zeros to exactly `N` bytes, or `None`
+    /// if the value is negative or doesn't fit.
+    pub fn to_fixed_bytes_be<const N: usize>(&self) -> Option<[u8; N]> {
+        let magnitude = match self.to_unsigned_bytes_be()? {
+            [0] => &[][..],
+            magnitude => magnitude,
+        };
+
+        if magnitude.len() > N {
+            return None;
+        }
+
+        let mut bytes = [0u8; N];
+        bytes[N - magnitude.len()..].copy_from_slice(magnitude);
+
+        Some(bytes)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0[0] & 0x80 != 0
+    }
+}
+
+impl DerSerializeContent for BigInt {
+    fn serialize_content<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        writer.write_all(&self.0)?;
+
+        Ok(self.0.len())
+    }
+}
+
+impl DerSerialize for BigInt {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let written = write_header(INTEGER, self.0.len(), writer)?;
+        writer.write_all(&self.0)?;
+
+        Ok(written + self.0.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.0.len())
+    }
+}
+
+impl DerDeserialize for BigInt {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        Ok(BigInt(read_integer(bytes)?.to_vec()))
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == INTEGER
+    }
+}
+
+/// An INTEGER's content bytes exactly as encoded, without the minimality
+/// check other integer types apply, for tooling that needs to inspect
+/// non-canonical input.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RawInteger(Vec<u8>);
+
+impl RawInteger {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The number of content bytes the encoding used.
+    pub fn content_len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the encoding has no redundant leading sign bytes, as DER
+    /// requires.
+    pub fn is_minimal(&self) -> bool {
+        minimal_integer(&self.0).len() == self.0.len()
+    }
+
+    /// The value, with any redundant leading bytes removed.
+    pub fn to_big_int(&self) -> BigInt {
+        BigInt::from_signed_bytes_be(&self.0)
+    }
+}
+
+// Written back exactly as decoded, so non-minimal input round-trips
+impl DerSerialize for RawInteger {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let written = write_header(INTEGER, self.0.len(), writer)?;
+        writer.write_all(&self.0)?;
+
+        Ok(written + self.0.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.0.len())
+    }
+}
+
+impl DerDeserialize for RawInteger {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        match read_contents(bytes, INTEGER)? {
+            [] => Err(DerError::InvalidEncoding),
+            contents => Ok(RawInteger(contents.to_vec())),
+        }
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == INTEGER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn big_int_enc_dec() {
+        let value = BigInt::from_unsigned_bytes_be(&[0x80; 20]);
+        assert!(!value.is_negative());
+        assert_eq!(value.as_signed_bytes_be().len(), 21);
+        assert_eq!(value.to_unsigned_bytes_be(), Some(&[0x80; 20][..]));
+
+        let buffer = value.to_der_vec();
+        assert_eq!(&buffer[..3], [0x02, 0x15, 0x00]);
+        assert_eq!(BigInt::deserialize(&mut &buffer[..]).unwrap(), value);
+
+        let negative = BigInt::from_signed_bytes_be(&[0xFF, 0xFF, 0x80]);
+        assert!(negative.is_negative());
+        assert_eq!(negative.to_der_vec(), (-128i32).to_der_vec());
+        assert_eq!(negative.to_unsigned_bytes_be(), None);
+
+        let zero = BigInt::from_unsigned_bytes_be(&[]);
+        assert_eq!(zero.to_der_vec(), [0x02, 0x01, 0x00]);
+        assert_eq!(zero.to_unsigned_bytes_be(), Some(&[0][..]));
+
+        assert_eq!(
+            BigInt::from_unsigned_bytes_be(&[0x01, 0x02]).to_fixed_bytes_be::<4>(),
+            Some([0x00, 0x00, 0x01, 0x02])
+        );
+        assert_eq!(zero.to_fixed_bytes_be::<2>(), Some([0, 0]));
+        assert_eq!(value.to_fixed_bytes_be::<19>(), None);
+        assert_eq!(negative.to_fixed_bytes_be::<4>(), None);
+
+        // Non-minimal
+        assert!(BigInt::deserialize(&mut &[0x02, 0x02, 0x00, 0x01][..]).is_err());
+    }
+
+    #[test]
+    fn big_int_magnitude() {
+        // The sign pad is added for a positive value with the top bit set
+        let positive = BigInt::from_magnitude(&[0x80, 0x00], false);
+        assert_eq!(positive.as_signed_bytes_be(), [0x00, 0x80, 0x00]);
+        assert_eq!(positive.magnitude_bytes(), [0x80, 0x00]);
+
+        let negative = BigInt::from_magnitude(&[0x80], true);
+        assert_eq!(negative.as_signed_bytes_be(), [0x80]);
+        assert_eq!(
+            negative,
+            BigInt::from_signed_bytes_be(&(-128i16).to_be_bytes())
+        );
+        assert_eq!(negative.magnitude_bytes(), [0x80]);
+
+        let negative = BigInt::from_magnitude(&[0x01, 0x00], true);
+        assert_eq!(negative.as_signed_bytes_be(), [0xFF, 0x00]);
+        assert!(negative.is_negative());
+        assert_eq!(negative.magnitude_bytes(), [0x01, 0x00]);
+
+        assert_eq!(
+            BigInt::from_magnitude(&[0x01], true).magnitude_bytes(),
+            [0x01]
+        );
+        assert_eq!(
+            BigInt::from_signed_bytes_be(&[0xFF]).magnitude_bytes(),
+            [0x01]
+        );
+
+        // Negative zero is zero
+        let zero = BigInt::from_magnitude(&[0x00], true);
+        assert!(!zero.is_negative());
+        assert_eq!(zero.as_signed_bytes_be(), [0x00]);
+        assert_eq!(zero.magnitude_bytes(), [0x00]);
+    }
+
+    #[test]
+    fn raw_integer() {
+        let buffer = [0x02, 0x02, 0x00, 0x05];
+        let raw = RawInteger::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(raw.content_len(), 2);
+        assert!(!raw.is_minimal());
+        assert_eq!(raw.to_big_int(), BigInt::from_unsigned_bytes_be(&[0x05]));
+        assert_eq!(raw.to_der_vec(), buffer);
+
+        let buffer = [0x02, 0x02, 0x00, 0x80];
+        let raw = RawInteger::deserialize(&mut &buffer[..]).unwrap();
+        assert!(raw.is_minimal());
+        assert_eq!(raw.as_bytes(), [0x00, 0x80]);
+
+        let raw = RawInteger::deserialize(&mut &[0x02, 0x02, 0xFF, 0x80][..]).unwrap();
+        assert!(!raw.is_minimal());
+        assert_eq!(raw.to_big_int().as_signed_bytes_be(), [0x80]);
+
+        assert!(RawInteger::deserialize(&mut &[0x02, 0x00][..]).is_err());
+    }
+}
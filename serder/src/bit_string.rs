@@ -0,0 +1,271 @@
+use crate::{
+    read_contents, tlv_len, write_header, DerDeserialize, DerError, DerSerialize, BIT_STRING,
+};
+use std::io::{self, Write};
+
+/// A BIT STRING. Bits are numbered from the most significant bit of the
+/// first byte, and the unused bits at the end of the last byte are zero.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BitString {
+    unused_bits: u8,
+    bytes: Vec<u8>,
+}
+
+impl BitString {
+    /// Builds a bit string from its bytes, the last `unused_bits` bits of
+    /// which aren't part of the value and must be zero.
+    pub fn new(bytes: impl Into<Vec<u8>>, unused_bits: u8) -> Result<Self, DerError> {
+        let bytes = bytes.into();
+
+        let valid = match bytes.last() {
+            Some(last) => unused_bits < 8 && last & ((1 << unused_bits) - 1) == 0,
+            None => unused_bits == 0,
+        };
+
+        if !valid {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(BitString { unused_bits, bytes })
+    }
+
+    /// A bit string using every bit of `bytes`.
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        BitString {
+            unused_bits: 0,
+            bytes: bytes.into(),
+        }
+    }
+
+    /// Encodes a named bit list, where bit `i` is `bits[i]`. As DER
+    /// requires, trailing zero bits are dropped.
+    pub fn from_named_bits(bits: &[bool]) -> Self {
+        let len = bits.iter().rposition(|bit| *bit).map_or(0, |last| last + 1);
+        let mut bytes = vec![0u8; len.div_ceil(8)];
+
+        for (i, _) in bits[..len].iter().enumerate().filter(|(_, bit)| **bit) {
+            bytes[i / 8] |= 0x80 >> (i % 8);
+        }
+
+        BitString {
+            unused_bits: (bytes.len() * 8 - len) as u8,
+            bytes,
+        }
+    }
+
+    pub fn unused_bits(&self) -> u8 {
+        self.unused_bits
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The number of bits in the value.
+    pub fn len(&self) -> usize {
+        self.bytes.len() * 8 - usize::from(self.unused_bits)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bit `index`, `false` if it's past the end of the value.
+    pub fn get_bit(&self, index: usize) -> bool {
+        index < self.len() && self.bytes[index / 8] & (0x80 >> (index % 8)) != 0
+    }
+
+    /// Decodes the content as the DER encoding of a single `T`, as with the
+    /// key inside a SubjectPublicKeyInfo. Every bit must be used.
+    pub fn inner_der<T: DerDeserialize>(&self) -> Result<T, DerError> {
+        if self.unused_bits != 0 {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        let mut bytes = &self.bytes[..];
+        let value = T::deserialize(&mut bytes)?;
+
+        if !bytes.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(value)
+    }
+
+    /// Each bit of the value in order, most significant bit of the first
+    /// byte first. The unused trailing bits aren't included.
+    pub fn bits(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len()).map(move |index| self.get_bit(index))
+    }
+}
+
+impl DerSerialize for BitString {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let written = write_header(BIT_STRING, 1 + self.bytes.len(), writer)?;
+        writer.write_all(&[self.unused_bits])?;
+        writer.write_all(&self.bytes)?;
+
+        Ok(written + 1 + self.bytes.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(1 + self.bytes.len())
+    }
+}
+
+impl DerDeserialize for BitString {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        match read_contents(bytes, BIT_STRING)?.split_first() {
+            Some((unused_bits, bytes)) => BitString::new(bytes, *unused_bits),
+            None => Err(DerError::InvalidEncoding),
+        }
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == BIT_STRING
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bit_string_enc_dec() {
+        let bits = BitString::new(vec![0b1010_0000], 4).unwrap();
+        assert_eq!(bits.len(), 4);
+        assert!(bits.get_bit(0) && !bits.get_bit(1) && bits.get_bit(2));
+        assert!(!bits.get_bit(100));
+
+        let buffer = bits.to_der_vec();
+        assert_eq!(buffer, [0x03, 0x02, 0x04, 0xA0]);
+        assert_eq!(BitString::deserialize(&mut &buffer[..]).unwrap(), bits);
+
+        assert_eq!(BitString::default().to_der_vec(), [0x03, 0x01, 0x00]);
+        assert_eq!(
+            BitString::from_bytes(vec![0xFF]).to_der_vec(),
+            [0x03, 0x02, 0x00, 0xFF]
+        );
+    }
+
+    #[test]
+    fn bit_string_rejects_invalid_forms() {
+        // No unused bits octet
+        assert!(BitString::deserialize(&mut &[0x03, 0x00][..]).is_err());
+        // Unused bits without any content
+        assert!(BitString::deserialize(&mut &[0x03, 0x01, 0x01][..]).is_err());
+        // More than 7 unused bits
+        assert!(BitString::deserialize(&mut &[0x03, 0x02, 0x08, 0x00][..]).is_err());
+        // Unused bits must be zero
+        assert!(BitString::deserialize(&mut &[0x03, 0x02, 0x04, 0xA8][..]).is_err());
+    }
+
+    #[test]
+    fn bit_iteration() {
+        let bits = BitString::new(vec![0b1010_1000], 3).unwrap();
+        assert_eq!(
+            bits.bits().collect::<Vec<_>>(),
+            [true, false, true, false, true]
+        );
+
+        let bits = BitString::from_bytes(vec![0x01, 0x80]);
+        assert_eq!(bits.bits().count(), 16);
+        assert_eq!(
+            bits.bits()
+                .enumerate()
+                .filter(|(_, bit)| *bit)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>(),
+            [7, 8]
+        );
+
+        assert_eq!(BitString::default().bits().count(), 0);
+    }
+
+    #[test]
+    fn named_bits() {
+        let bits = BitString::from_named_bits(&[true, false, false, false, false, true, false]);
+        assert_eq!(bits.to_der_vec(), [0x03, 0x02, 0x02, 0x84]);
+        assert_eq!(bits.len(), 6);
+
+        assert_eq!(
+            BitString::from_named_bits(&[false; 9]).to_der_vec(),
+            [0x03, 0x01, 0x00]
+        );
+        assert_eq!(
+            BitString::from_named_bits(&[
+                false, false, false, false, false, false, false, false, true
+            ])
+            .to_der_vec(),
+            [0x03, 0x03, 0x07, 0x00, 0x80]
+        );
+    }
+
+    #[derive(Debug, Default, PartialEq, crate::DerSerialize, crate::DerDeserialize)]
+    #[der(bit_flags)]
+    struct KeyUsage {
+        digital_signature: bool,
+        non_repudiation: bool,
+        key_encipherment: bool,
+        data_encipherment: bool,
+        key_agreement: bool,
+        key_cert_sign: bool,
+        crl_sign: bool,
+        encipher_only: bool,
+        decipher_only: bool,
+    }
+
+    #[test]
+    fn bit_flags_derive() {
+        let usage = KeyUsage {
+            digital_signature: true,
+            key_cert_sign: true,
+            ..KeyUsage::default()
+        };
+
+        // Bits 6 through 8 are dropped as trailing zeros
+        let buffer = usage.to_der_vec();
+        assert_eq!(buffer, [0x03, 0x02, 0x02, 0x84]);
+        assert_eq!(KeyUsage::deserialize(&mut &buffer[..]).unwrap(), usage);
+
+        let buffer = KeyUsage::default().to_der_vec();
+        assert_eq!(buffer, [0x03, 0x01, 0x00]);
+        assert_eq!(
+            KeyUsage::deserialize(&mut &buffer[..]).unwrap(),
+            KeyUsage::default()
+        );
+
+        let usage = KeyUsage {
+            decipher_only: true,
+            ..KeyUsage::default()
+        };
+        let buffer = usage.to_der_vec();
+        assert_eq!(buffer, [0x03, 0x03, 0x07, 0x00, 0x80]);
+        assert_eq!(KeyUsage::deserialize(&mut &buffer[..]).unwrap(), usage);
+
+        // Trailing zero bit
+        assert!(KeyUsage::deserialize(&mut &[0x03, 0x02, 0x01, 0x84][..]).is_err());
+        // Bit past the named ones
+        assert!(KeyUsage::deserialize(&mut &[0x03, 0x03, 0x06, 0x00, 0x40][..]).is_err());
+    }
+
+    #[test]
+    fn der_in_bit_string() {
+        use crate::x509::RsaPublicKey;
+
+        let key = RsaPublicKey::from_components(&[0xC3; 64], &[0x01, 0x00, 0x01]);
+        let bits = BitString::from_bytes(key.to_der_vec());
+
+        let buffer = bits.to_der_vec();
+        let decoded = BitString::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(decoded.inner_der::<RsaPublicKey>().unwrap(), key);
+        assert!(decoded.inner_der::<u32>().is_err());
+
+        // The nested encoding must use whole bytes
+        let bits = BitString::new(vec![0x05, 0x00], 1).unwrap();
+        assert!(matches!(
+            bits.inner_der::<crate::Null>(),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+//! A runner for DER conformance vectors: a directory with `valid` and
+//! `invalid` subdirectories of `.der` files, each holding one encoding.
+
+use crate::ber::{der_canonicalize, verify_der};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// A vector whose outcome didn't match the directory it was in.
+#[derive(Debug)]
+pub struct Mismatch {
+    pub path: PathBuf,
+    pub expected_valid: bool,
+    pub reason: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected = if self.expected_valid {
+            "valid"
+        } else {
+            "invalid"
+        };
+
+        write!(
+            f,
+            "{}: expected {}, {}",
+            self.path.display(),
+            expected,
+            self.reason
+        )
+    }
+}
+
+/// Runs every vector under `dir`, returning the ones that failed.
+///
+/// A valid vector must pass `verify_der`. An invalid one must fail it, and if
+/// `der_canonicalize` accepts it, the canonical form must then pass.
+pub fn run_corpus(dir: &Path) -> io::Result<Vec<Mismatch>> {
+    let mut mismatches = Vec::new();
+
+    for (subdirectory, expected_valid) in [("valid", true), ("invalid", false)] {
+        let mut paths = fs::read_dir(dir.join(subdirectory))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "der"));
+        paths.sort();
+
+        for path in paths {
+            let bytes = fs::read(&path)?;
+
+            if let Some(reason) = check(&bytes, expected_valid) {
+                mismatches.push(Mismatch {
+                    path,
+                    expected_valid,
+                    reason,
+                });
+            }
+        }
+    }
+
+    Ok(mismatches)
+}
+
+fn check(bytes: &[u8], expected_valid: bool) -> Option<String> {
+    match (verify_der(bytes), expected_valid) {
+        (Ok(()), true) => None,
+        (Err(e), true) => Some(format!("verify_der failed: {}", e)),
+        (Ok(()), false) => Some(String::from("verify_der accepted it")),
+        (Err(_), false) => match der_canonicalize(bytes) {
+            Ok(der) => verify_der(&der)
+                .err()
+                .map(|e| format!("its canonical form failed verify_der: {}", e)),
+            Err(_) => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conformance_vectors() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("conformance");
+        let mismatches = run_corpus(&dir).unwrap();
+
+        assert!(
+            mismatches.is_empty(),
+            "{}",
+            mismatches
+                .iter()
+                .map(Mismatch::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        assert!(fs::read_dir(dir.join("invalid")).unwrap().count() >= 10);
+    }
+}
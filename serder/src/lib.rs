@@ -1,20 +1,281 @@
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+extern crate self as serder;
+
+pub use serder_derive::{DerDeserialize, DerSerialize};
+
+/// Declares a struct encoded as a SEQUENCE of its fields, in order, deriving
+/// `DerSerialize` and `DerDeserialize`:
+///
+/// ```
+/// serder::der_struct! {
+///     #[derive(Debug)]
+///     pub SubjectPublicKeyInfo {
+///         pub algorithm: serder::AnyTlv,
+///         pub subject_public_key: serder::BitString,
+///     }
+/// }
+/// ```
+///
+/// Attributes, including `#[der(...)]`, pass through to the struct and its
+/// fields.
+#[macro_export]
+macro_rules! der_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive($crate::DerSerialize, $crate::DerDeserialize)]
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+    };
+}
+
+mod any;
+pub mod ber;
+mod big_int;
+mod bit_string;
+#[cfg(any(test, feature = "test-util"))]
+pub mod conformance;
+mod null;
+mod octet_string;
+mod oid;
+pub mod oids;
+pub mod pem;
+mod schema;
+mod set_of;
+mod size;
+mod strings;
+mod tagged;
+mod time;
+mod value;
+pub mod x509;
+
+pub use any::{AnyTlv, TaggedAny};
+pub use big_int::{BigInt, RawInteger};
+pub use bit_string::BitString;
+pub use null::Null;
+pub use octet_string::OctetString;
+pub use oid::{FixedOid, KnownOid, ObjectIdentifier};
+pub use schema::{decode_with_schema, Schema};
+pub use set_of::SetOf;
+pub use size::{SizeBounded, SizeConstrained};
+pub use strings::{Ia5Str, Ia5String, PrintableStr, PrintableString, Utf8Str, Utf8String};
+pub use tagged::{explicit_tag, read_explicit, Explicit};
+pub use time::{GeneralizedTime, Time, UtcTime};
+pub use value::{decode_value, decode_value_with, Value};
+
 use std::{
     convert::TryInto,
     io::{self, Write},
 };
 
 pub trait DerSerialize {
-    fn serialize<W: Write>(&self, writer: W) -> io::Result<usize>;
+    /// Writes the encoding, returning its length. Composite encoders pass the
+    /// same `writer` on to each component.
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize>;
+
+    /// The number of bytes `serialize` will write for this value.
+    fn encoded_len(&self) -> usize {
+        self.serialize(&mut io::sink())
+            .expect("serializing into a sink never fails")
+    }
+
+    /// Serializes into a new `Vec`, sized up front with `encoded_len`.
+    fn to_der_vec(&self) -> Vec<u8> {
+        self.to_der_vec_with_capacity(self.encoded_len())
+    }
+
+    /// Appends the encoding to `buffer`, returning the number of bytes added.
+    fn serialize_into(&self, buffer: &mut Vec<u8>) -> io::Result<usize> {
+        self.serialize(buffer)
+    }
+
+    fn to_der_vec_with_capacity(&self, capacity: usize) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(capacity);
+        self.serialize(&mut buffer)
+            .expect("serializing into a Vec never fails");
+
+        buffer
+    }
+}
+
+/// An object safe counterpart of `DerSerialize`, implemented for every
+/// `DerSerialize` type, so that values of different types can be serialized
+/// through `Box<dyn ErasedDerSerialize>`.
+pub trait ErasedDerSerialize {
+    fn erased_serialize(&self, writer: &mut dyn Write) -> io::Result<usize>;
+
+    fn erased_encoded_len(&self) -> usize;
+}
+
+impl<T: DerSerialize> ErasedDerSerialize for T {
+    fn erased_serialize(&self, writer: &mut dyn Write) -> io::Result<usize> {
+        self.serialize(writer)
+    }
+
+    fn erased_encoded_len(&self) -> usize {
+        self.encoded_len()
+    }
+}
+
+impl DerSerialize for Box<dyn ErasedDerSerialize + '_> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        // `dyn Write` needs a sized writer to point at
+        (**self).erased_serialize(&mut &mut *writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        (**self).erased_encoded_len()
+    }
+}
+
+impl<T: DerSerialize + ?Sized> DerSerialize for &T {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        (**self).serialize(writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+}
+
+/// Replaces the contents of `buffer` with the encoding of `value`, so one
+/// buffer can be reused across serializations. The buffer only grows when
+/// an encoding is larger than any before it.
+pub fn encode_into_reused<T: DerSerialize + ?Sized>(
+    value: &T,
+    buffer: &mut Vec<u8>,
+) -> io::Result<usize> {
+    buffer.clear();
+    buffer.reserve(value.encoded_len());
+
+    value.serialize(buffer)
+}
+
+/// Serializes `value` into the front of `out`, returning the number of bytes
+/// written. Nothing is written if the encoding doesn't fit.
+pub fn serialize_fixed<T: DerSerialize + ?Sized>(
+    value: &T,
+    out: &mut [u8],
+) -> Result<usize, DerError> {
+    let needed = value.encoded_len();
+
+    if needed > out.len() {
+        return Err(DerError::BufferTooSmall {
+            needed,
+            available: out.len(),
+        });
+    }
+
+    Ok(value.serialize(&mut &mut out[..])?)
+}
+
+/// A type encoded as a single TLV that can write just its content bytes,
+/// leaving the identifier and length to a wrapper such as an implicit tag.
+pub trait DerSerializeContent: DerSerialize {
+    fn serialize_content<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize>;
+}
+
+/// Size in bytes of the DER encoding of `value`, without serializing it.
+pub fn der_size<T: DerSerialize>(value: &T) -> usize {
+    value.encoded_len()
 }
 
 #[derive(Debug)]
 pub enum DerError {
+    /// An output buffer was smaller than the encoding written to it
+    BufferTooSmall {
+        needed: usize,
+        available: usize,
+    },
+    /// Another error annotated with where in a schema it occurred
+    Context {
+        context: &'static str,
+        source: Box<DerError>,
+    },
     IntValueTooLarge,
     InvalidEncoding,
     Io(io::Error),
+    /// A value's size was outside its SIZE constraint
+    SizeOutOfRange {
+        size: usize,
+        min: usize,
+        max: usize,
+    },
+    /// A SEQUENCE OF or SET OF held more elements than allowed
+    TooManyElements {
+        max: usize,
+    },
     UnexpectedEof,
-    UnexpectedTag,
+    /// The identifier octet didn't match the type being decoded. `expected`
+    /// is `None` when several tags would have been accepted.
+    UnexpectedTag {
+        expected: Option<Tag>,
+        found: Tag,
+    },
+}
+
+impl std::fmt::Display for DerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DerError::BufferTooSmall { needed, available } => write!(
+                f,
+                "encoding needs {} bytes but the buffer holds {}",
+                needed, available
+            ),
+            DerError::Context { context, source } => write!(f, "{}: {}", context, source),
+            DerError::IntValueTooLarge => write!(f, "integer value too large for the target type"),
+            DerError::InvalidEncoding => write!(f, "invalid DER encoding"),
+            DerError::Io(e) => write!(f, "I/O error: {}", e),
+            DerError::SizeOutOfRange { size, min, max } => {
+                write!(f, "size {} is outside the range {}..{}", size, min, max)
+            }
+            DerError::TooManyElements { max } => write!(f, "more than {} elements", max),
+            DerError::UnexpectedEof => write!(f, "unexpected end of input"),
+            DerError::UnexpectedTag {
+                expected: Some(expected),
+                found,
+            } => write!(f, "unexpected tag: expected {}, found {}", expected, found),
+            DerError::UnexpectedTag {
+                expected: None,
+                found,
+            } => write!(f, "unexpected tag {}", found),
+        }
+    }
+}
+
+impl std::error::Error for DerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DerError::Context { source, .. } => Some(source),
+            DerError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl DerError {
+    /// The underlying error, with any context stripped.
+    pub fn root_cause(&self) -> &DerError {
+        match self {
+            DerError::Context { source, .. } => source.root_cause(),
+            e => e,
+        }
+    }
+}
+
+/// Annotates the error of `result`, if any, with the context returned by `f`.
+pub fn context<T, F: FnOnce() -> &'static str>(
+    result: Result<T, DerError>,
+    f: F,
+) -> Result<T, DerError> {
+    result.map_err(|source| DerError::Context {
+        context: f(),
+        source: Box::new(source),
+    })
 }
 
 impl From<std::num::TryFromIntError> for DerError {
@@ -33,6 +294,23 @@ impl From<io::Error> for DerError {
     }
 }
 
+impl From<DerError> for io::Error {
+    fn from(e: DerError) -> Self {
+        match e {
+            DerError::Io(e) => e,
+            e => {
+                let kind = match e.root_cause() {
+                    DerError::Io(e) => e.kind(),
+                    DerError::BufferTooSmall { .. } => io::ErrorKind::WriteZero,
+                    _ => io::ErrorKind::InvalidData,
+                };
+
+                io::Error::new(kind, e)
+            }
+        }
+    }
+}
+
 impl From<std::convert::Infallible> for DerError {
     fn from(_: std::convert::Infallible) -> Self {
         unreachable!()
@@ -41,15 +319,33 @@ impl From<std::convert::Infallible> for DerError {
 
 pub trait DerDeserialize: Sized {
     fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError>;
+
+    /// Whether an encoding beginning with the identifier octet `tag` is a
+    /// value of this type. Used to detect absent OPTIONAL components and to
+    /// match SET components regardless of their order.
+    fn matches_tag(tag: u8) -> bool;
+}
+
+/// Decoding into a value that borrows from the input, such as a string
+/// slice, instead of copying out of it.
+pub trait DerDeserializeBorrowed<'a>: Sized {
+    fn deserialize_borrowed(bytes: &mut &'a [u8]) -> Result<Self, DerError>;
+}
+
+/// Decodes one `T` from the front of `bytes`, returning it with whatever
+/// follows, for reading consecutive messages out of a borrowed buffer.
+pub fn decode_remaining<T: DerDeserialize>(bytes: &[u8]) -> Result<(T, &[u8]), DerError> {
+    let mut remaining = bytes;
+    let value = T::deserialize(&mut remaining)?;
+
+    Ok((value, remaining))
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Length(u32);
 
 impl Length {
-    pub fn new(n: u32) -> Self {
-        assert!(n > 0);
-
+    pub const fn new(n: u32) -> Self {
         Length(n)
     }
 
@@ -63,7 +359,7 @@ impl Length {
 }
 
 impl DerSerialize for Length {
-    fn serialize<W: Write>(&self, mut writer: W) -> io::Result<usize> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
         let written = if self.0 > 127 {
             // Long form, use the smallest encoding for the value
             //
@@ -71,13 +367,13 @@ impl DerSerialize for Length {
             let start = bytes.iter().take_while(|b| **b == 0).count();
             let len = 4 - start;
 
-            writer.write_u8(0x80 | len as u8)?;
+            writer.write_all(&[0x80 | len as u8])?;
             writer.write_all(&bytes[start..])?;
 
             len + 1
         } else {
             // Short form
-            writer.write_u8(self.0 as u8)?;
+            writer.write_all(&[self.0 as u8])?;
 
             1
         };
@@ -88,19 +384,57 @@ impl DerSerialize for Length {
 
 impl DerDeserialize for Length {
     fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
-        let first_byte = bytes.read_u8()?;
+        let first_byte = read_u8(bytes)?;
 
         if first_byte > 127 {
             let mask_len = (first_byte & 0b0111_1111) as usize;
-            let len = bytes.read_uint::<BigEndian>(mask_len)?.try_into()?;
 
-            Ok(Length(len))
+            // The indefinite length form is only allowed in BER
+            if mask_len == 0 {
+                return Err(DerError::InvalidEncoding);
+            }
+
+            if mask_len > 8 {
+                return Err(DerError::IntValueTooLarge);
+            }
+
+            if bytes.len() < mask_len {
+                return Err(DerError::UnexpectedEof);
+            }
+
+            let (len_bytes, rest) = bytes.split_at(mask_len);
+
+            // The long form must use as few octets as possible, so it never
+            // starts with a zero octet or encodes a length below 128
+            if len_bytes[0] == 0 || (mask_len == 1 && len_bytes[0] < 0x80) {
+                return Err(DerError::InvalidEncoding);
+            }
+
+            *bytes = rest;
+
+            let len = len_bytes
+                .iter()
+                .fold(0u64, |len, &byte| len << 8 | u64::from(byte));
+
+            Ok(Length(len.try_into()?))
         } else {
             Ok(Length(u32::from(first_byte)))
         }
     }
+
+    fn matches_tag(_: u8) -> bool {
+        false
+    }
 }
 
+/// Builder for a single identifier octet.
+///
+/// The value given to `new` supplies the low six bits of the octet: the tag
+/// number in bits 0-4 and the constructed flag in bit 5, so it must be below
+/// 64. The class methods replace bits 6-7 and the form methods replace bit 5,
+/// leaving the rest untouched. Tag numbers of 31 and above need the
+/// high-tag-number form, see `write_tag_number`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Tag(u8);
 
 impl Tag {
@@ -108,20 +442,37 @@ impl Tag {
         Tag(tag_number)
     }
 
+    /// Like `new`, but returns `None` if `tag_number` has class bits set.
+    pub const fn try_new(tag_number: u8) -> Option<Self> {
+        if tag_number < 64 {
+            Some(Tag(tag_number))
+        } else {
+            None
+        }
+    }
+
+    // Class bits in `self` would be silently discarded, which is always a
+    // mistake in the value given to `new`
+    const fn with_class(self, class: u8) -> Self {
+        debug_assert!(self.0 < 64, "tag number must be below 64");
+
+        Tag(self.0 & 0b0011_1111 | class)
+    }
+
     pub const fn universal(self) -> Self {
-        Tag(self.0 & 0b0011_1111)
+        self.with_class(0b0000_0000)
     }
 
     pub const fn application(self) -> Self {
-        Tag(self.0 & 0b0011_1111 | 0b0100_0000)
+        self.with_class(0b0100_0000)
     }
 
     pub const fn context_specific(self) -> Self {
-        Tag(self.0 & 0b0011_1111 | 0b1000_0000)
+        self.with_class(0b1000_0000)
     }
 
     pub const fn private(self) -> Self {
-        Tag(self.0 & 0b0011_1111 | 0b1100_0000)
+        self.with_class(0b1100_0000)
     }
 
     pub const fn primitive(self) -> Self {
@@ -135,6 +486,70 @@ impl Tag {
     pub const fn into_tag_value(self) -> u8 {
         self.0
     }
+
+    /// The ASN.1 name of a universal class tag, such as `"INTEGER"`. `None`
+    /// for other classes and unassigned numbers.
+    pub fn universal_name(&self) -> Option<&'static str> {
+        if self.0 >> 6 != 0 {
+            return None;
+        }
+
+        let name = match self.0 & 0b0001_1111 {
+            0 => "EOC",
+            1 => "BOOLEAN",
+            2 => "INTEGER",
+            3 => "BIT STRING",
+            4 => "OCTET STRING",
+            5 => "NULL",
+            6 => "OBJECT IDENTIFIER",
+            7 => "ObjectDescriptor",
+            8 => "EXTERNAL",
+            9 => "REAL",
+            10 => "ENUMERATED",
+            11 => "EMBEDDED PDV",
+            12 => "UTF8String",
+            13 => "RELATIVE-OID",
+            14 => "TIME",
+            16 => "SEQUENCE",
+            17 => "SET",
+            18 => "NumericString",
+            19 => "PrintableString",
+            20 => "TeletexString",
+            21 => "VideotexString",
+            22 => "IA5String",
+            23 => "UTCTime",
+            24 => "GeneralizedTime",
+            25 => "GraphicString",
+            26 => "VisibleString",
+            27 => "GeneralString",
+            28 => "UniversalString",
+            29 => "CHARACTER STRING",
+            30 => "BMPString",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+}
+
+// ASN.1 notation, `[UNIVERSAL 2]` or `[0]` for context-specific tags. Tag
+// numbers in the high-tag-number form show as 31.
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number = self.0 & 0b0001_1111;
+        let form = if self.0 & 0b0010_0000 != 0 {
+            " constructed"
+        } else {
+            ""
+        };
+
+        match self.0 >> 6 {
+            0 => write!(f, "[UNIVERSAL {}]{}", number, form),
+            1 => write!(f, "[APPLICATION {}]{}", number, form),
+            2 => write!(f, "[{}]{}", number, form),
+            _ => write!(f, "[PRIVATE {}]{}", number, form),
+        }
+    }
 }
 
 pub const BOOLEAN: u8 = Tag::new(1).universal().primitive().into_tag_value();
@@ -143,27 +558,354 @@ pub const BIT_STRING: u8 = Tag::new(3).universal().primitive().into_tag_value();
 pub const OCTET_STRING: u8 = Tag::new(4).universal().primitive().into_tag_value();
 pub const NULL: u8 = Tag::new(5).universal().primitive().into_tag_value();
 pub const OBJECT_IDENTIFIER: u8 = Tag::new(6).universal().primitive().into_tag_value();
+pub const UTF8_STRING: u8 = Tag::new(12).universal().primitive().into_tag_value();
+pub const SEQUENCE: u8 = Tag::new(16).universal().constructed().into_tag_value();
+pub const SET: u8 = Tag::new(17).universal().constructed().into_tag_value();
+pub const PRINTABLE_STRING: u8 = Tag::new(19).universal().primitive().into_tag_value();
+pub const IA5_STRING: u8 = Tag::new(22).universal().primitive().into_tag_value();
+pub const UTC_TIME: u8 = Tag::new(23).universal().primitive().into_tag_value();
+pub const GENERALIZED_TIME: u8 = Tag::new(24).universal().primitive().into_tag_value();
+
+/// How deeply constructed values may nest before the functions that walk
+/// input without a schema, such as `skip_tlv`, `ber::der_canonicalize` and
+/// `ber::detect_rules`, reject it as `DerError::InvalidEncoding` rather than
+/// recursing further.
+pub const MAX_DEPTH: usize = 128;
+
+fn content_length(len: usize) -> io::Result<Length> {
+    len.try_into().map(Length::new).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "content too large for DER length",
+        )
+    })
+}
+
+/// Total size of a TLV with a single byte tag and `content_len` bytes of
+/// content.
+pub fn tlv_len(content_len: usize) -> usize {
+    let length_len = match content_len {
+        0..=127 => 1,
+        _ => 1 + (std::mem::size_of::<usize>() - content_len.leading_zeros() as usize / 8),
+    };
+
+    1 + length_len + content_len
+}
+
+/// Writes the tag and length for a value with `content_len` bytes of
+/// content, returning the number of bytes written.
+pub fn write_header<W: Write + ?Sized>(
+    tag: u8,
+    content_len: usize,
+    writer: &mut W,
+) -> io::Result<usize> {
+    writer.write_all(&[tag])?;
+
+    Ok(1 + content_length(content_len)?.serialize(writer)?)
+}
+
+/// Writes the length octets for `n` bytes of content, returning the number of
+/// bytes written. Lengths that don't fit in a `u32` are an error.
+pub fn encode_length(n: usize, mut writer: impl Write) -> io::Result<usize> {
+    content_length(n)?.serialize(&mut writer)
+}
+
+/// Reads a definite, minimally encoded length.
+pub fn decode_length(bytes: &mut &[u8]) -> Result<usize, DerError> {
+    Ok(Length::deserialize(bytes)?.into_usize())
+}
+
+/// Buffers the content written by `body`, then writes it framed by `tag` and
+/// its length. Returns the total number of bytes written.
+pub fn write_length_prefixed<W, F>(tag: u8, writer: &mut W, body: F) -> io::Result<usize>
+where
+    W: Write + ?Sized,
+    F: FnOnce(&mut Vec<u8>) -> io::Result<()>,
+{
+    let mut contents = Vec::new();
+    body(&mut contents)?;
+
+    let written = write_header(tag, contents.len(), writer)?;
+    writer.write_all(&contents)?;
+
+    Ok(written + contents.len())
+}
+
+/// Reads the tag and length of the next value, checking the tag is
+/// `expected_tag`, and returns its content bytes.
+pub fn read_contents<'a>(bytes: &mut &'a [u8], expected_tag: u8) -> Result<&'a [u8], DerError> {
+    let tag = read_u8(bytes)?;
+
+    if tag != expected_tag {
+        return Err(DerError::UnexpectedTag {
+            expected: Some(Tag(expected_tag)),
+            found: Tag(tag),
+        });
+    }
+
+    let length = Length::deserialize(bytes)?.into_usize();
+
+    if length > bytes.len() {
+        return Err(DerError::UnexpectedEof);
+    }
+
+    let (contents, rest) = bytes.split_at(length);
+    *bytes = rest;
+
+    Ok(contents)
+}
+
+/// Writes `value` in base 128, most significant group first, with the high
+/// bit set on every byte but the last.
+pub fn write_base128<W: Write + ?Sized>(value: u64, writer: &mut W) -> io::Result<usize> {
+    let groups = (64 - value.leading_zeros() as usize).max(1).div_ceil(7);
+
+    for i in (0..groups).rev() {
+        let group = (value >> (i * 7)) as u8 & 0x7F;
+        let continuation = if i == 0 { 0 } else { 0x80 };
+
+        writer.write_all(&[group | continuation])?;
+    }
+
+    Ok(groups)
+}
+
+/// Reads a base 128 value as written by `write_base128`, rejecting
+/// non-minimal encodings with a leading `0x80` byte.
+pub fn read_base128(bytes: &mut &[u8]) -> Result<u64, DerError> {
+    if bytes.first() == Some(&0x80) {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    let mut value = 0u64;
+
+    loop {
+        let byte = read_u8(bytes)?;
+
+        if value > u64::MAX >> 7 {
+            return Err(DerError::IntValueTooLarge);
+        }
+
+        value = value << 7 | u64::from(byte & 0x7F);
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+/// Writes an identifier with the class and form bits of `class_and_form` and
+/// the given tag number, using the high-tag-number form for numbers of 31 and
+/// above.
+pub fn write_tag_number<W: Write + ?Sized>(
+    class_and_form: u8,
+    number: u64,
+    writer: &mut W,
+) -> io::Result<usize> {
+    let class_and_form = class_and_form & 0b1110_0000;
+
+    if number < 31 {
+        writer.write_all(&[class_and_form | number as u8])?;
+
+        Ok(1)
+    } else {
+        writer.write_all(&[class_and_form | 0b0001_1111])?;
+
+        Ok(1 + write_base128(number, writer)?)
+    }
+}
+
+/// Reads an identifier, returning its class and form bits along with the tag
+/// number.
+pub fn read_tag_number(bytes: &mut &[u8]) -> Result<(u8, u64), DerError> {
+    let first = read_u8(bytes)?;
+    let class_and_form = first & 0b1110_0000;
+
+    if first & 0b0001_1111 != 0b0001_1111 {
+        return Ok((class_and_form, u64::from(first & 0b0001_1111)));
+    }
+
+    let number = read_base128(bytes)?;
+
+    // The low tag number form must be used where possible
+    if number < 31 {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    Ok((class_and_form, number))
+}
+
+/// Advances `bytes` past the next TLV without decoding it, returning the
+/// number of bytes skipped. A constructed value with the BER indefinite
+/// length form is skipped through its matching EOC marker.
+pub fn skip_tlv(bytes: &mut &[u8]) -> Result<usize, DerError> {
+    skip_tlv_nested(bytes, 0)
+}
+
+// `depth` counts the indefinite length values the next TLV is inside of
+fn skip_tlv_nested(bytes: &mut &[u8], depth: usize) -> Result<usize, DerError> {
+    let start = *bytes;
+    let mut rest = start;
+    let (class_and_form, _) = read_tag_number(&mut rest)?;
+
+    match ber::read_length(&mut rest)? {
+        Some(length) => {
+            if length > rest.len() {
+                return Err(DerError::UnexpectedEof);
+            }
+
+            rest = &rest[length..];
+        }
+        None => {
+            // Only constructed values may use the indefinite form
+            if class_and_form & 0b0010_0000 == 0 {
+                return Err(DerError::InvalidEncoding);
+            }
+
+            if depth == MAX_DEPTH {
+                return Err(DerError::InvalidEncoding);
+            }
+
+            while !ber::is_eoc(rest) {
+                skip_tlv_nested(&mut rest, depth + 1)?;
+            }
+
+            rest = &rest[2..];
+        }
+    }
+
+    let skipped = start.len() - rest.len();
+    *bytes = rest;
+
+    Ok(skipped)
+}
+
+/// The size of the identifier and length octets at the front of `bytes`,
+/// including high tag numbers and long form lengths. The content doesn't
+/// need to be present.
+pub fn header_len(bytes: &[u8]) -> Result<usize, DerError> {
+    Ok(read_header(bytes)?.0)
+}
+
+// The header size and content length of the TLV at the front of `bytes`
+fn read_header(bytes: &[u8]) -> Result<(usize, usize), DerError> {
+    let mut rest = bytes;
+    read_tag_number(&mut rest)?;
+    let length = Length::deserialize(&mut rest)?.into_usize();
+
+    Ok((bytes.len() - rest.len(), length))
+}
+
+// Splits the next complete TLV off the front of `bytes`, along with the length
+// of its header
+fn split_tlv<'a>(bytes: &mut &'a [u8]) -> Result<(&'a [u8], usize), DerError> {
+    let start = *bytes;
+    let (header_len, length) = read_header(start)?;
+
+    if length > start.len() - header_len {
+        return Err(DerError::UnexpectedEof);
+    }
+
+    let (tlv, rest) = start.split_at(header_len + length);
+    *bytes = rest;
+
+    Ok((tlv, header_len))
+}
+
+// Takes the next byte off the front of `bytes`
+fn read_u8(bytes: &mut &[u8]) -> Result<u8, DerError> {
+    let (&first, rest) = bytes.split_first().ok_or(DerError::UnexpectedEof)?;
+    *bytes = rest;
+
+    Ok(first)
+}
+
+// Interprets INTEGER content bytes, already checked by `read_integer`, as a
+// signed value
+fn read_be_int(contents: &[u8]) -> Result<i128, DerError> {
+    if contents.len() > 16 {
+        return Err(DerError::IntValueTooLarge);
+    }
+
+    let sign = if contents[0] & 0x80 != 0 { 0xFF } else { 0x00 };
+    let mut value = [sign; 16];
+    value[16 - contents.len()..].copy_from_slice(contents);
+
+    Ok(i128::from_be_bytes(value))
+}
+
+// Interprets INTEGER content bytes, already checked by `read_integer`, as an
+// unsigned value, treating a negative value as out of range
+fn read_be_uint(contents: &[u8]) -> Result<u128, DerError> {
+    let contents = match contents {
+        [first, ..] if first & 0x80 != 0 => return Err(DerError::IntValueTooLarge),
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        contents => contents,
+    };
+
+    if contents.len() > 16 {
+        return Err(DerError::IntValueTooLarge);
+    }
+
+    let mut value = [0; 16];
+    value[16 - contents.len()..].copy_from_slice(contents);
+
+    Ok(u128::from_be_bytes(value))
+}
+
+// Strips the sign extension bytes that don't change a two's complement value
+fn minimal_integer(mut bytes: &[u8]) -> &[u8] {
+    while let [first, second, ..] = bytes {
+        match (first, second & 0x80) {
+            (0x00, 0x00) | (0xFF, 0x80) => bytes = &bytes[1..],
+            _ => break,
+        }
+    }
+
+    bytes
+}
+
+/// Reads the content of an INTEGER, rejecting an empty or non-minimal
+/// encoding: a leading `0x00` followed by a byte with a clear top bit or a
+/// leading `0xFF` followed by a byte with the top bit set.
+pub fn read_integer<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], DerError> {
+    let contents = read_contents(bytes, INTEGER)?;
+
+    if contents.is_empty() || minimal_integer(contents).len() != contents.len() {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    Ok(contents)
+}
 
 macro_rules! int_encode {
     ($($t:ty),+) => {$(
-        impl DerSerialize for $t {
-            fn serialize<W: Write>(&self, mut writer: W) -> io::Result<usize> {
-                let mut written = 0;
+        impl DerSerializeContent for $t {
+            fn serialize_content<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+                // One extra byte of sign extension so unsigned values with the
+                // top bit set keep a 0x00 pad
+                let mut bytes = [0u8; std::mem::size_of::<$t>() + 1];
+                let value = self.to_be_bytes();
+
+                if <$t>::MIN != 0 && value[0] & 0x80 != 0 {
+                    bytes[0] = 0xFF;
+                }
 
-                writer.write_u8(INTEGER)?;
-                written += 1;
+                bytes[1..].copy_from_slice(&value);
+                let contents = minimal_integer(&bytes);
+                writer.write_all(contents)?;
 
-                let bytes = self.to_be_bytes();
-                let mut start = bytes.iter().take_while(|b| **b == 0).count();
-                let mut len = std::mem::size_of::<$t>() - start;
+                Ok(contents.len())
+            }
+        }
 
-                if len == 0 {
-                    len = 1;
-                    start = bytes.len() - 1;
-                }
+        impl DerSerialize for $t {
+            fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+                let mut contents = [0u8; std::mem::size_of::<$t>() + 1];
+                let len = self.serialize_content(&mut &mut contents[..])?;
 
-                written += Length::new(len as u32).serialize(&mut writer)?;
-                writer.write_all(&bytes[start..])?;
+                let written = write_header(INTEGER, len, writer)?;
+                writer.write_all(&contents[..len])?;
 
                 Ok(written + len)
             }
@@ -175,19 +917,11 @@ macro_rules! int_decode {
     ($($t:ty),+) => {$(
         impl DerDeserialize for $t {
             fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
-                let tag = bytes.read_u8()?;
-
-                if tag != INTEGER {
-                    return Err(DerError::UnexpectedTag);
-                }
-
-                let length = Length::deserialize(bytes)?.into_usize();
+                Ok(read_be_int(read_integer(bytes)?)?.try_into()?)
+            }
 
-                if length < std::mem::size_of::<$t>() {
-                    Ok(bytes.read_uint128::<BigEndian>(length)?.try_into()?)
-                } else {
-                    Ok(bytes.read_int128::<BigEndian>(length)?.try_into()?)
-                }
+            fn matches_tag(tag: u8) -> bool {
+                tag == INTEGER
             }
         }
     )+}
@@ -197,65 +931,553 @@ macro_rules! uint_decode {
     ($($t:ty),+) => {$(
         impl DerDeserialize for $t {
             fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
-                let tag = bytes.read_u8()?;
-
-                if tag != INTEGER {
-                    return Err(DerError::UnexpectedTag);
-                }
-
-                let length = Length::deserialize(bytes)?.into_usize();
+                Ok(read_be_uint(read_integer(bytes)?)?.try_into()?)
+            }
 
-                Ok(bytes.read_uint128::<BigEndian>(length)?.try_into()?)
+            fn matches_tag(tag: u8) -> bool {
+                tag == INTEGER
             }
         }
     )+}
 }
 
-int_encode!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
-int_decode!(i8, i16, i32, i64, i128);
-uint_decode!(u8, u16, u32, u64, u128);
+// DER only allows 0xFF for TRUE
+impl DerSerialize for bool {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let written = write_header(BOOLEAN, 1, writer)?;
+        writer.write_all(&[if *self { 0xFF } else { 0x00 }])?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        Ok(written + 1)
+    }
+}
 
-    macro_rules! zero {
-        ($n:ident) => {
-            for i in $n.iter_mut() {
-                *i = 0;
-            }
-        };
+impl DerDeserialize for bool {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        match read_contents(bytes, BOOLEAN)? {
+            [0x00] => Ok(false),
+            [0xFF] => Ok(true),
+            _ => Err(DerError::InvalidEncoding),
+        }
     }
 
-    #[test]
-    fn length_encoding() {
-        // Length serialization
-        let mut slice = [0u8; 5];
+    fn matches_tag(tag: u8) -> bool {
+        tag == BOOLEAN
+    }
+}
 
-        assert_eq!(Length::new(1).serialize(&mut slice[..]).unwrap(), 1);
-        assert_eq!(slice, [1, 0, 0, 0, 0]);
-        zero!(slice);
+/// A boolean carried as an INTEGER 0 or 1, for schemas that use one in place
+/// of a BOOLEAN.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IntBool(pub bool);
 
-        assert_eq!(Length::new(128).serialize(&mut slice[..]).unwrap(), 2);
-        assert_eq!(slice, [0x81, 0x80, 0x00, 0x00, 0x00]);
+impl DerSerialize for IntBool {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        u8::from(self.0).serialize(writer)
+    }
+}
+
+impl DerDeserialize for IntBool {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        match read_integer(bytes)? {
+            [0] => Ok(IntBool(false)),
+            [1] => Ok(IntBool(true)),
+            _ => Err(DerError::InvalidEncoding),
+        }
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == INTEGER
+    }
+}
+
+/// A fixed-point decimal carried as an INTEGER: the raw value is the number
+/// times `10^SCALE`. The scale is part of the schema, not the encoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Scaled<const SCALE: u32>(pub i64);
+
+impl<const SCALE: u32> Scaled<SCALE> {
+    /// Rounds `value` to the nearest multiple of `10^-SCALE`.
+    pub fn from_f64(value: f64) -> Self {
+        Scaled((value * 10f64.powi(SCALE as i32)).round() as i64)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / 10f64.powi(SCALE as i32)
+    }
+}
+
+impl<const SCALE: u32> DerSerialize for Scaled<SCALE> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        self.0.serialize(writer)
+    }
+}
+
+impl<const SCALE: u32> DerDeserialize for Scaled<SCALE> {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        i64::deserialize(bytes).map(Scaled)
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == INTEGER
+    }
+}
+
+/// An unsigned INTEGER written without the `0x00` sign pad, so values with
+/// the top bit set come out looking negative.
+///
+/// This isn't valid DER and is only for peers that read INTEGERs as unsigned
+/// and reject the pad. Plain `u64` writes the correct form. Decoding accepts
+/// the content with or without the pad.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnpaddedUnsigned(pub u64);
+
+impl DerSerialize for UnpaddedUnsigned {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let bytes = self.0.to_be_bytes();
+        let leading_zeros = (self.0.leading_zeros() / 8).min(7) as usize;
+        let contents = &bytes[leading_zeros..];
+
+        let written = write_header(INTEGER, contents.len(), writer)?;
+        writer.write_all(contents)?;
+
+        Ok(written + contents.len())
+    }
+}
+
+impl DerDeserialize for UnpaddedUnsigned {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        let contents = match read_contents(bytes, INTEGER)? {
+            [] => return Err(DerError::InvalidEncoding),
+            [0, rest @ ..] if !rest.is_empty() => rest,
+            contents => contents,
+        };
+
+        if contents.len() > 8 {
+            return Err(DerError::IntValueTooLarge);
+        }
+
+        let mut value = [0; 8];
+        value[8 - contents.len()..].copy_from_slice(contents);
+
+        Ok(UnpaddedUnsigned(u64::from_be_bytes(value)))
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == INTEGER
+    }
+}
+
+// `usize` and `isize` use the target's pointer width, so a value that decodes
+// on a 64-bit target can be IntValueTooLarge on a 32-bit one
+int_encode!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize);
+int_decode!(i8, i16, i32, i64, i128, isize);
+uint_decode!(u8, u16, u32, u64, u128, usize);
+
+// SEQUENCE OF
+impl<T: DerSerialize> DerSerialize for Vec<T> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let content_len: usize = self.iter().map(DerSerialize::encoded_len).sum();
+        let mut written = write_header(SEQUENCE, content_len, writer)?;
+
+        for element in self {
+            written += element.serialize(writer)?;
+        }
+
+        Ok(written)
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.iter().map(DerSerialize::encoded_len).sum())
+    }
+}
+
+impl<T: DerDeserialize> DerDeserialize for Vec<T> {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        let mut contents = read_contents(bytes, SEQUENCE)?;
+        let mut elements = Vec::new();
+
+        while !contents.is_empty() {
+            elements.push(T::deserialize(&mut contents)?);
+        }
+
+        Ok(elements)
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == SEQUENCE
+    }
+}
+
+// SEQUENCE SIZE (N) OF, for every element type including `u8`
+impl<T: DerSerialize, const N: usize> DerSerialize for [T; N] {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let content_len: usize = self.iter().map(DerSerialize::encoded_len).sum();
+        let mut written = write_header(SEQUENCE, content_len, writer)?;
+
+        for element in self {
+            written += element.serialize(writer)?;
+        }
+
+        Ok(written)
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.iter().map(DerSerialize::encoded_len).sum())
+    }
+}
+
+// More than `N` elements is `TooManyElements`, fewer is `InvalidEncoding`
+impl<T: DerDeserialize, const N: usize> DerDeserialize for [T; N] {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        let elements = read_sequence_of_bounded(bytes, N)?;
+
+        elements
+            .try_into()
+            .map_err(|_: Vec<T>| DerError::InvalidEncoding)
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == SEQUENCE
+    }
+}
+
+/// Writes the items of `items` as a SEQUENCE OF without collecting them into
+/// a `Vec` first.
+///
+/// The iterator is only walked once, so the elements are encoded into a
+/// buffer and written after the header once their total length is known.
+pub fn serialize_sequence_of<I, T, W>(items: I, writer: &mut W) -> io::Result<usize>
+where
+    I: IntoIterator<Item = T>,
+    T: DerSerialize,
+    W: Write + ?Sized,
+{
+    write_length_prefixed(SEQUENCE, writer, |contents| {
+        for item in items {
+            item.serialize(contents)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Decodes a SEQUENCE OF, failing with `DerError::TooManyElements` as soon
+/// as more than `max` elements are found.
+pub fn read_sequence_of_bounded<T: DerDeserialize>(
+    bytes: &mut &[u8],
+    max: usize,
+) -> Result<Vec<T>, DerError> {
+    let mut contents = read_contents(bytes, SEQUENCE)?;
+    let mut elements = Vec::new();
+
+    while !contents.is_empty() {
+        if elements.len() == max {
+            return Err(DerError::TooManyElements { max });
+        }
+
+        elements.push(T::deserialize(&mut contents)?);
+    }
+
+    Ok(elements)
+}
+
+/// Decodes a SEQUENCE OF, keeping every element that decodes and the errors
+/// for those that don't. Bad elements are skipped by their TLV length, so
+/// decoding only stops early if an element's header can't be read.
+pub fn decode_sequence_of_lenient<T: DerDeserialize>(bytes: &[u8]) -> (Vec<T>, Vec<DerError>) {
+    let mut elements = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut contents = match read_contents(&mut &bytes[..], SEQUENCE) {
+        Ok(contents) => contents,
+        Err(e) => return (elements, vec![e]),
+    };
+
+    while !contents.is_empty() {
+        let mut tlv = match split_tlv(&mut contents) {
+            Ok((tlv, _)) => tlv,
+            Err(e) => {
+                errors.push(e);
+                break;
+            }
+        };
+
+        match T::deserialize(&mut tlv) {
+            Ok(_) if !tlv.is_empty() => errors.push(DerError::InvalidEncoding),
+            Ok(element) => elements.push(element),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    (elements, errors)
+}
+
+// OPTIONAL, an absent value encodes as nothing
+impl<T: DerSerialize> DerSerialize for Option<T> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        match self {
+            Some(value) => value.serialize(writer),
+            None => Ok(0),
+        }
+    }
+}
+
+impl<T: DerDeserialize> DerDeserialize for Option<T> {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        match bytes.first() {
+            Some(&tag) if T::matches_tag(tag) => T::deserialize(bytes).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        T::matches_tag(tag)
+    }
+}
+
+/// A value whose complete DER encoding is computed once and written verbatim
+/// on every call to `serialize`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PreEncoded(Vec<u8>);
+
+impl PreEncoded {
+    pub fn new<T: DerSerialize>(value: &T) -> Self {
+        PreEncoded(value.to_der_vec())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Decodes the cached encoding as a `T`.
+    pub fn decode<T: DerDeserialize>(&self) -> Result<T, DerError> {
+        T::deserialize(&mut &self.0[..])
+    }
+}
+
+impl DerSerialize for PreEncoded {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        writer.write_all(&self.0)?;
+
+        Ok(self.0.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl DerDeserialize for PreEncoded {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        Ok(PreEncoded(split_tlv(bytes)?.0.to_vec()))
+    }
+
+    fn matches_tag(_: u8) -> bool {
+        true
+    }
+}
+
+/// A decoded value together with the exact bytes it was decoded from, such
+/// as a TBSCertificate whose signature covers its original encoding. The
+/// bytes are written back verbatim, so non-canonical input is preserved.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Captured<T> {
+    value: T,
+    bytes: Vec<u8>,
+}
+
+impl<T: DerSerialize> Captured<T> {
+    /// Captures the DER encoding of a value built rather than decoded.
+    pub fn new(value: T) -> Self {
+        let bytes = value.to_der_vec();
+
+        Captured { value, bytes }
+    }
+}
+
+impl<T> Captured<T> {
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// The complete encoding the value was decoded from.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_parts(self) -> (T, Vec<u8>) {
+        (self.value, self.bytes)
+    }
+}
+
+impl<T> DerSerialize for Captured<T> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        writer.write_all(&self.bytes)?;
+
+        Ok(self.bytes.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl<T: DerDeserialize> DerDeserialize for Captured<T> {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        let start = *bytes;
+        let value = T::deserialize(bytes)?;
+        let consumed = start.len() - bytes.len();
+
+        Ok(Captured {
+            value,
+            bytes: start[..consumed].to_vec(),
+        })
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        T::matches_tag(tag)
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+
+    pub use crate::oid::{oid_bytes, oid_len};
+
+    /// Writes the components of a SET, ordered by their tags as DER requires.
+    pub fn serialize_set<W: Write + ?Sized>(
+        mut components: Vec<Vec<u8>>,
+        writer: &mut W,
+    ) -> io::Result<usize> {
+        // Class, then the full tag number, ignoring the constructed bit
+        components.sort_by_key(|component| {
+            read_tag_number(&mut &component[..])
+                .ok()
+                .map(|(class_and_form, number)| (class_and_form & 0b1100_0000, number))
+        });
+
+        let content_len = components.iter().map(Vec::len).sum();
+        let written = write_header(SET, content_len, writer)?;
+
+        for component in &components {
+            writer.write_all(component)?;
+        }
+
+        Ok(written + content_len)
+    }
+
+    /// Checks a BIT STRING is a canonical named bit list with no bits past
+    /// the `count` named ones.
+    pub fn check_named_bits(bits: &BitString, count: usize) -> Result<(), DerError> {
+        // DER drops trailing zero bits from named bit lists
+        let minimal = bits.is_empty() || bits.get_bit(bits.len() - 1);
+
+        if !minimal || bits.len() > count {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(())
+    }
+
+    /// The value of a DEFAULT component to encode, `None` when it is the
+    /// default and so must be left out.
+    pub fn non_default<T: Default + PartialEq>(value: &T) -> Option<&T> {
+        if *value == T::default() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    /// Rejects a DEFAULT component that was encoded with its default value.
+    pub fn reject_default<T: Default + PartialEq>(value: T) -> Result<T, DerError> {
+        if value == T::default() {
+            Err(DerError::InvalidEncoding)
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Decodes a DEFAULT component of a SEQUENCE, using the default value
+    /// when the next element isn't one.
+    pub fn deserialize_default<T>(bytes: &mut &[u8]) -> Result<T, DerError>
+    where
+        T: DerDeserialize + Default + PartialEq,
+    {
+        match bytes.first() {
+            Some(&tag) if T::matches_tag(tag) => T::deserialize(bytes).and_then(reject_default),
+            _ => Ok(T::default()),
+        }
+    }
+
+    /// Finds which of the SET component slots the element at the front of
+    /// `contents` belongs to by its tag, `None` if no slot accepts it.
+    pub fn set_component_position(
+        contents: &[u8],
+        matchers: &[fn(u8) -> bool],
+    ) -> Result<Option<usize>, DerError> {
+        let tag = *contents.first().ok_or(DerError::UnexpectedEof)?;
+
+        Ok(matchers.iter().position(|matches| matches(tag)))
+    }
+
+    /// The error for an identifier no alternative of a CHOICE accepts.
+    pub fn unexpected_tag(tag: u8) -> DerError {
+        DerError::UnexpectedTag {
+            expected: None,
+            found: Tag(tag),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! zero {
+        ($n:ident) => {
+            for i in $n.iter_mut() {
+                *i = 0;
+            }
+        };
+    }
+
+    #[test]
+    fn length_encoding() {
+        // Length serialization
+        let mut slice = [0u8; 5];
+
+        assert_eq!(Length::new(1).serialize(&mut &mut slice[..]).unwrap(), 1);
+        assert_eq!(slice, [1, 0, 0, 0, 0]);
+        zero!(slice);
+
+        assert_eq!(Length::new(128).serialize(&mut &mut slice[..]).unwrap(), 2);
+        assert_eq!(slice, [0x81, 0x80, 0x00, 0x00, 0x00]);
         zero!(slice);
 
         assert_eq!(
-            Length::new(0x0000_A5B5).serialize(&mut slice[..]).unwrap(),
+            Length::new(0x0000_A5B5)
+                .serialize(&mut &mut slice[..])
+                .unwrap(),
             3
         );
         assert_eq!(slice, [0x82, 0xA5, 0xB5, 0x00, 0x00]);
         zero!(slice);
 
         assert_eq!(
-            Length::new(0x00A5_B5C5).serialize(&mut slice[..]).unwrap(),
+            Length::new(0x00A5_B5C5)
+                .serialize(&mut &mut slice[..])
+                .unwrap(),
             4
         );
         assert_eq!(slice, [0x83, 0xA5, 0xB5, 0xC5, 0x00]);
         zero!(slice);
 
         assert_eq!(
-            Length::new(0xA5B5_C5D5).serialize(&mut slice[..]).unwrap(),
+            Length::new(0xA5B5_C5D5)
+                .serialize(&mut &mut slice[..])
+                .unwrap(),
             5
         );
         assert_eq!(slice, [0x84, 0xA5, 0xB5, 0xC5, 0xD5]);
@@ -285,6 +1507,106 @@ mod tests {
 
         let slice = [0x85, 0xAB, 0xCD, 0xEF, 0x88, 0x99];
         assert!(Length::deserialize(&mut &slice[..]).is_err());
+
+        let slice = [0x89, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert!(Length::deserialize(&mut &slice[..]).is_err());
+
+        let slice = [0x80];
+        assert!(matches!(
+            Length::deserialize(&mut &slice[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn length_truncated_long_form() {
+        // Declares 3 length bytes but only has 1
+        let slice = [0x83, 0xAB];
+        assert!(matches!(
+            Length::deserialize(&mut &slice[..]),
+            Err(DerError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            u32::deserialize(&mut &[0x02, 0x83, 0xAB][..]),
+            Err(DerError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn length_short_long_boundary() {
+        assert_eq!(Length::deserialize(&mut &[0x7F][..]).unwrap(), Length(127));
+        assert_eq!(
+            Length::deserialize(&mut &[0x81, 0x80][..]).unwrap(),
+            Length(128)
+        );
+        assert_eq!(Length::new(127).to_der_vec(), [0x7F]);
+        assert_eq!(Length::new(128).to_der_vec(), [0x81, 0x80]);
+        assert_eq!(Length::new(255).to_der_vec(), [0x81, 0xFF]);
+        assert_eq!(Length::new(256).to_der_vec(), [0x82, 0x01, 0x00]);
+
+        let mut contents = vec![0x04, 0x7F];
+        contents.resize(2 + 127, 0xAA);
+        assert_eq!(OctetString::new(vec![0xAA; 127]).to_der_vec(), contents);
+        let mut contents = vec![0x04, 0x81, 0x80];
+        contents.resize(3 + 128, 0xAA);
+        assert_eq!(OctetString::new(vec![0xAA; 128]).to_der_vec(), contents);
+        assert_eq!(
+            OctetString::deserialize(&mut &contents[..])
+                .unwrap()
+                .as_bytes(),
+            &[0xAA; 128][..]
+        );
+
+        // Non-minimal long forms
+        for slice in [&[0x81, 0x7F][..], &[0x81, 0x00], &[0x82, 0x00, 0x80]] {
+            assert!(matches!(
+                Length::deserialize(&mut &slice[..]),
+                Err(DerError::InvalidEncoding)
+            ));
+        }
+    }
+
+    #[test]
+    fn usize_lengths() {
+        let cases: [(usize, &[u8]); 6] = [
+            (1, &[0x01]),
+            (127, &[0x7F]),
+            (128, &[0x81, 0x80]),
+            (0x0000_A5B5, &[0x82, 0xA5, 0xB5]),
+            (0x00A5_B5C5, &[0x83, 0xA5, 0xB5, 0xC5]),
+            (0xA5B5_C5D5, &[0x84, 0xA5, 0xB5, 0xC5, 0xD5]),
+        ];
+
+        for (n, encoding) in cases {
+            let mut buffer = Vec::new();
+            assert_eq!(encode_length(n, &mut buffer).unwrap(), encoding.len());
+            assert_eq!(buffer, encoding);
+
+            let mut slice = [0u8; 5];
+            encode_length(n, &mut slice[..]).unwrap();
+            assert_eq!(slice[..encoding.len()], *encoding);
+
+            let mut bytes = encoding;
+            assert_eq!(decode_length(&mut bytes).unwrap(), n);
+            assert!(bytes.is_empty());
+        }
+
+        let mut sink = io::sink();
+        assert_eq!(
+            encode_length(u32::MAX as usize + 1, &mut sink)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        assert!(matches!(
+            decode_length(&mut &[0x81, 0x05][..]),
+            Err(DerError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            decode_length(&mut &[0x80][..]),
+            Err(DerError::InvalidEncoding)
+        ));
     }
 
     macro_rules! integer_enc_dec {
@@ -305,5 +1627,1419 @@ mod tests {
         }
     }
 
-    integer_enc_dec!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128);
+    integer_enc_dec!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize);
+
+    #[test]
+    fn pointer_width_integers() {
+        let count: usize = 1000;
+        let buffer = count.to_der_vec();
+        assert_eq!(buffer, 1000u32.to_der_vec());
+        assert_eq!(usize::deserialize(&mut &buffer[..]).unwrap(), count);
+
+        let buffer = (-1isize).to_der_vec();
+        assert_eq!(buffer, [0x02, 0x01, 0xFF]);
+        assert_eq!(isize::deserialize(&mut &buffer[..]).unwrap(), -1);
+        assert!(matches!(
+            usize::deserialize(&mut &buffer[..]),
+            Err(DerError::IntValueTooLarge)
+        ));
+
+        let buffer = u128::MAX.to_der_vec();
+        assert!(matches!(
+            usize::deserialize(&mut &buffer[..]),
+            Err(DerError::IntValueTooLarge)
+        ));
+    }
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn pointer_width_overflow_on_32_bit() {
+        let buffer = (u32::MAX as u64 + 1).to_der_vec();
+        assert!(matches!(
+            usize::deserialize(&mut &buffer[..]),
+            Err(DerError::IntValueTooLarge)
+        ));
+
+        let buffer = (i32::MIN as i64 - 1).to_der_vec();
+        assert!(matches!(
+            isize::deserialize(&mut &buffer[..]),
+            Err(DerError::IntValueTooLarge)
+        ));
+    }
+
+    #[test]
+    fn sequence_of_enc_dec() {
+        let empty: Vec<u32> = vec![];
+        assert_eq!(empty.to_der_vec(), [0x30, 0x00]);
+        assert_eq!(
+            Vec::<u32>::deserialize(&mut &[0x30, 0x00][..]).unwrap(),
+            empty
+        );
+
+        let values = vec![1u8, 2, 3];
+        let buffer = values.to_der_vec();
+        assert_eq!(
+            buffer,
+            [0x30, 0x09, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02, 0x02, 0x01, 0x03]
+        );
+        assert_eq!(Vec::<u8>::deserialize(&mut &buffer[..]).unwrap(), values);
+
+        let nested: Vec<Vec<i64>> = (0..100)
+            .map(|_| vec![rand::random(), rand::random()])
+            .collect();
+        let buffer = nested.to_der_vec();
+        assert_eq!(
+            Vec::<Vec<i64>>::deserialize(&mut &buffer[..]).unwrap(),
+            nested
+        );
+
+        // Declared length runs past the end of the input
+        assert!(Vec::<u8>::deserialize(&mut &[0x30, 0x05, 0x02, 0x01, 0x01][..]).is_err());
+    }
+
+    #[test]
+    fn fixed_size_sequence_of() {
+        let values = [1u32, 300, 70000];
+        let buffer = values.to_der_vec();
+        assert_eq!(buffer, values.to_vec().to_der_vec());
+        assert_eq!(values.encoded_len(), buffer.len());
+        assert_eq!(<[u32; 3]>::deserialize(&mut &buffer[..]).unwrap(), values);
+
+        assert!(matches!(
+            <[u32; 2]>::deserialize(&mut &buffer[..]),
+            Err(DerError::TooManyElements { max: 2 })
+        ));
+        assert!(matches!(
+            <[u32; 4]>::deserialize(&mut &buffer[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+
+        assert_eq!(<[u8; 0]>::deserialize(&mut &[0x30, 0x00][..]).unwrap(), []);
+    }
+
+    #[test]
+    fn header_sizes() {
+        assert_eq!(
+            header_len(&[0x30, 0x82, 0x01, 0x2C, 0x02, 0x01]).unwrap(),
+            4
+        );
+        assert_eq!(header_len(&[0x05, 0x00]).unwrap(), 2);
+        // High tag number
+        assert_eq!(header_len(&[0x9F, 0x81, 0x00, 0x81, 0x80]).unwrap(), 5);
+
+        assert!(matches!(
+            header_len(&[0x30, 0x82, 0x01]),
+            Err(DerError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            header_len(&[0x30, 0x81, 0x05]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn serialize_into_cursor() {
+        #[derive(DerSerialize, DerDeserialize, Debug, PartialEq)]
+        struct Entry {
+            id: u32,
+            values: Vec<u64>,
+        }
+
+        let entries: Vec<Entry> = (0..10_000)
+            .map(|id| Entry {
+                id,
+                values: (0..id % 7).map(u64::from).collect(),
+            })
+            .collect();
+
+        // Something already at the front of the stream is kept
+        let mut cursor = io::Cursor::new(vec![0xEE]);
+        cursor.set_position(1);
+        let written = entries.serialize(&mut cursor).unwrap();
+
+        let expected = entries.to_der_vec();
+        assert!(expected.len() > 0xFFFF);
+        assert_eq!(written, expected.len());
+        assert_eq!(cursor.position() as usize, 1 + expected.len());
+        assert_eq!(cursor.get_ref()[0], 0xEE);
+        assert_eq!(&cursor.get_ref()[1..], &expected[..]);
+        assert_eq!(
+            Vec::<Entry>::deserialize(&mut &cursor.get_ref()[1..]).unwrap(),
+            entries
+        );
+    }
+
+    #[test]
+    fn sequence_of_from_iterator() {
+        let mut buffer = Vec::new();
+        let written = serialize_sequence_of((0..5).map(|i| i * 100u32), &mut buffer).unwrap();
+        assert_eq!(written, buffer.len());
+        assert_eq!(buffer, vec![0u32, 100, 200, 300, 400].to_der_vec());
+
+        buffer.clear();
+        serialize_sequence_of(std::iter::empty::<u8>(), &mut buffer).unwrap();
+        assert_eq!(buffer, [0x30, 0x00]);
+    }
+
+    #[test]
+    fn der_size_matches_encoding() {
+        assert_eq!(der_size(&0u8), 0u8.to_der_vec().len());
+        assert_eq!(der_size(&u128::MAX), u128::MAX.to_der_vec().len());
+
+        let empty: Vec<u32> = vec![];
+        assert_eq!(der_size(&empty), empty.to_der_vec().len());
+
+        let values: Vec<u64> = (0..500).map(|_| rand::random()).collect();
+        assert_eq!(der_size(&values), values.to_der_vec().len());
+
+        let nested: Vec<Vec<i32>> = (0..50).map(|i| vec![i; i as usize]).collect();
+        assert_eq!(der_size(&nested), nested.to_der_vec().len());
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    #[der(set)]
+    struct OptionalSet {
+        values: Option<Vec<u8>>,
+        number: Option<u32>,
+    }
+
+    #[test]
+    fn set_optional_components() {
+        // Components appear in reverse tag order, SEQUENCE before INTEGER
+        let buffer = [0x31, 0x08, 0x30, 0x03, 0x02, 0x01, 0x07, 0x02, 0x01, 0x2A];
+        let set = OptionalSet::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(
+            set,
+            OptionalSet {
+                values: Some(vec![7]),
+                number: Some(42),
+            }
+        );
+
+        // Encoding orders the components by tag
+        assert_eq!(
+            set.to_der_vec(),
+            [0x31, 0x08, 0x02, 0x01, 0x2A, 0x30, 0x03, 0x02, 0x01, 0x07]
+        );
+        assert_eq!(der_size(&set), 10);
+
+        let buffer = [0x31, 0x03, 0x02, 0x01, 0x2A];
+        let set = OptionalSet::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(
+            set,
+            OptionalSet {
+                values: None,
+                number: Some(42),
+            }
+        );
+
+        let buffer = [0x31, 0x00];
+        let set = OptionalSet::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(
+            set,
+            OptionalSet {
+                values: None,
+                number: None,
+            }
+        );
+        assert_eq!(set.to_der_vec(), buffer);
+
+        // BOOLEAN isn't a component of the set
+        let buffer = [0x31, 0x06, 0x01, 0x01, 0xFF, 0x02, 0x01, 0x2A];
+        assert!(matches!(
+            OptionalSet::deserialize(&mut &buffer[..]),
+            Err(DerError::UnexpectedTag { .. })
+        ));
+
+        // Duplicate component
+        let buffer = [0x31, 0x06, 0x02, 0x01, 0x2A, 0x02, 0x01, 0x2A];
+        assert!(OptionalSet::deserialize(&mut &buffer[..]).is_err());
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    #[der(set)]
+    struct ExtensibleSet {
+        number: u32,
+        #[der(unknown)]
+        unknown: Vec<AnyTlv>,
+    }
+
+    #[test]
+    fn set_unknown_components() {
+        // An OCTET STRING this version doesn't know about, before the INTEGER
+        // and after a BOOLEAN
+        let buffer = [
+            0x31, 0x0B, 0x01, 0x01, 0xFF, 0x02, 0x01, 0x2A, 0x04, 0x03, 0x01, 0x02, 0x03,
+        ];
+        let set = ExtensibleSet::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(set.number, 42);
+        assert_eq!(
+            set.unknown,
+            [
+                AnyTlv::new(BOOLEAN, &[0xFF]),
+                AnyTlv::new(OCTET_STRING, &[0x01, 0x02, 0x03])
+            ]
+        );
+
+        assert_eq!(set.to_der_vec(), buffer);
+        assert_eq!(set.encoded_len(), buffer.len());
+
+        // Unknowns added out of order are written in tag order
+        let set = ExtensibleSet {
+            number: 1,
+            unknown: vec![AnyTlv::new(NULL, &[]), AnyTlv::new(BOOLEAN, &[0x00])],
+        };
+        assert_eq!(
+            set.to_der_vec(),
+            [0x31, 0x08, 0x01, 0x01, 0x00, 0x02, 0x01, 0x01, 0x05, 0x00]
+        );
+
+        // High tag numbers all share a first octet, so are ordered by number
+        let high = |encoding: &[u8]| AnyTlv::deserialize(&mut &encoding[..]).unwrap();
+        let set = ExtensibleSet {
+            number: 1,
+            unknown: vec![
+                high(&[0x9F, 0x81, 0x48, 0x00]),
+                high(&[0xBF, 0x28, 0x00]),
+                high(&[0x9F, 0x23, 0x00]),
+            ],
+        };
+        let buffer = set.to_der_vec();
+        assert_eq!(
+            buffer,
+            [
+                0x31, 0x0D, 0x02, 0x01, 0x01, 0x9F, 0x23, 0x00, 0xBF, 0x28, 0x00, 0x9F, 0x81, 0x48,
+                0x00
+            ]
+        );
+        assert_eq!(
+            ExtensibleSet::deserialize(&mut &buffer[..])
+                .unwrap()
+                .unknown,
+            [
+                high(&[0x9F, 0x23, 0x00]),
+                high(&[0xBF, 0x28, 0x00]),
+                high(&[0x9F, 0x81, 0x48, 0x00])
+            ]
+        );
+
+        // Known components are still required
+        assert!(ExtensibleSet::deserialize(&mut &[0x31, 0x02, 0x05, 0x00][..]).is_err());
+    }
+
+    #[test]
+    fn boolean_enc_dec() {
+        assert_eq!(true.to_der_vec(), [0x01, 0x01, 0xFF]);
+        assert_eq!(false.to_der_vec(), [0x01, 0x01, 0x00]);
+        assert!(bool::deserialize(&mut &[0x01, 0x01, 0xFF][..]).unwrap());
+        assert!(!bool::deserialize(&mut &[0x01, 0x01, 0x00][..]).unwrap());
+
+        // Only 0xFF is TRUE in DER
+        assert!(bool::deserialize(&mut &[0x01, 0x01, 0x01][..]).is_err());
+        assert!(bool::deserialize(&mut &[0x01, 0x02, 0xFF, 0xFF][..]).is_err());
+        assert!(bool::deserialize(&mut &[0x01, 0x00][..]).is_err());
+    }
+
+    #[test]
+    fn integer_content_only() {
+        let mut buffer = Vec::new();
+        assert_eq!(300u32.serialize_content(&mut buffer).unwrap(), 2);
+        assert_eq!(buffer, [0x01, 0x2C]);
+
+        buffer.clear();
+        200u8.serialize_content(&mut buffer).unwrap();
+        assert_eq!(buffer, [0x00, 0xC8]);
+
+        buffer.clear();
+        (-1i64).serialize_content(&mut buffer).unwrap();
+        assert_eq!(buffer, [0xFF]);
+    }
+
+    #[test]
+    fn fixed_buffer_serialization() {
+        let value = Inner {
+            first: 1,
+            second: 2,
+        };
+        let encoding = value.to_der_vec();
+
+        let mut out = [0u8; 16];
+        assert_eq!(serialize_fixed(&value, &mut out).unwrap(), encoding.len());
+        assert_eq!(out[..encoding.len()], encoding[..]);
+        assert!(out[encoding.len()..].iter().all(|&b| b == 0));
+
+        let mut exact = vec![0u8; encoding.len()];
+        assert_eq!(serialize_fixed(&value, &mut exact).unwrap(), encoding.len());
+        assert_eq!(exact, encoding);
+
+        let mut small = [0u8; 4];
+        let e = serialize_fixed(&value, &mut small).unwrap_err();
+        assert!(matches!(
+            e,
+            DerError::BufferTooSmall {
+                needed: 8,
+                available: 4
+            }
+        ));
+        assert_eq!(
+            e.to_string(),
+            "encoding needs 8 bytes but the buffer holds 4"
+        );
+        assert_eq!(small, [0; 4]);
+        assert_eq!(io::Error::from(e).kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn decoding_with_remainder() {
+        let buffer = [0x02, 0x01, 0x07, 0x05, 0x00, 0x01, 0x01, 0xFF];
+
+        let (value, rest) = decode_remaining::<u8>(&buffer).unwrap();
+        assert_eq!(value, 7);
+        assert_eq!(rest, &buffer[3..]);
+
+        let (_, rest) = decode_remaining::<Null>(rest).unwrap();
+        let (flag, rest) = decode_remaining::<bool>(rest).unwrap();
+        assert!(flag);
+        assert!(rest.is_empty());
+
+        assert!(decode_remaining::<u8>(rest).is_err());
+    }
+
+    #[test]
+    fn scaled_decimal() {
+        let value = Scaled::<2>::from_f64(1.23);
+        assert_eq!(value, Scaled(123));
+        assert_eq!(value.to_der_vec(), 123u8.to_der_vec());
+
+        let buffer = value.to_der_vec();
+        let decoded = Scaled::<2>::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(decoded, value);
+        assert!((decoded.to_f64() - 1.23).abs() < 1e-9);
+
+        assert_eq!(Scaled::<3>::from_f64(-0.5), Scaled(-500));
+        assert_eq!(Scaled::<0>(42).to_f64(), 42.0);
+    }
+
+    #[test]
+    fn unpadded_unsigned() {
+        assert_eq!(0x80u8.to_der_vec(), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(UnpaddedUnsigned(0x80).to_der_vec(), [0x02, 0x01, 0x80]);
+        assert_eq!(UnpaddedUnsigned(0).to_der_vec(), [0x02, 0x01, 0x00]);
+        assert_eq!(
+            UnpaddedUnsigned(u64::MAX).to_der_vec(),
+            [0x02, 0x08, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+
+        for buffer in [&[0x02, 0x01, 0x80][..], &[0x02, 0x02, 0x00, 0x80][..]] {
+            assert_eq!(
+                UnpaddedUnsigned::deserialize(&mut &buffer[..]).unwrap(),
+                UnpaddedUnsigned(0x80)
+            );
+        }
+
+        let buffer = u128::MAX.to_der_vec();
+        assert!(matches!(
+            UnpaddedUnsigned::deserialize(&mut &buffer[..]),
+            Err(DerError::IntValueTooLarge)
+        ));
+    }
+
+    #[test]
+    fn int_bool() {
+        assert_eq!(
+            IntBool::deserialize(&mut &[0x02, 0x01, 0x01][..]).unwrap(),
+            IntBool(true)
+        );
+        assert_eq!(
+            IntBool::deserialize(&mut &[0x02, 0x01, 0x00][..]).unwrap(),
+            IntBool(false)
+        );
+        assert!(matches!(
+            IntBool::deserialize(&mut &[0x02, 0x01, 0x02][..]),
+            Err(DerError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            IntBool::deserialize(&mut &[0x01, 0x01, 0xFF][..]),
+            Err(DerError::UnexpectedTag { .. })
+        ));
+
+        assert_eq!(IntBool(true).to_der_vec(), [0x02, 0x01, 0x01]);
+        assert_eq!(IntBool(false).to_der_vec(), [0x02, 0x01, 0x00]);
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    #[der(set)]
+    struct DefaultSet {
+        #[der(default)]
+        flag: bool,
+        number: u32,
+    }
+
+    #[test]
+    fn set_default_components() {
+        let set = DefaultSet {
+            flag: false,
+            number: 42,
+        };
+        let buffer = set.to_der_vec();
+        assert_eq!(buffer, [0x31, 0x03, 0x02, 0x01, 0x2A]);
+        assert_eq!(der_size(&set), buffer.len());
+        assert_eq!(DefaultSet::deserialize(&mut &buffer[..]).unwrap(), set);
+
+        let set = DefaultSet {
+            flag: true,
+            number: 42,
+        };
+        let buffer = set.to_der_vec();
+        assert_eq!(buffer, [0x31, 0x06, 0x01, 0x01, 0xFF, 0x02, 0x01, 0x2A]);
+        assert_eq!(DefaultSet::deserialize(&mut &buffer[..]).unwrap(), set);
+
+        // Encoded default value
+        let buffer = [0x31, 0x06, 0x01, 0x01, 0x00, 0x02, 0x01, 0x2A];
+        assert!(DefaultSet::deserialize(&mut &buffer[..]).is_err());
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    #[der(set)]
+    struct RequiredSet {
+        number: u8,
+        values: Vec<u8>,
+    }
+
+    #[test]
+    fn set_missing_required_component() {
+        let buffer = [0x31, 0x03, 0x02, 0x01, 0x2A];
+        let e = RequiredSet::deserialize(&mut &buffer[..]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "while decoding RequiredSet.values: unexpected end of input"
+        );
+        assert!(matches!(e.root_cause(), DerError::UnexpectedEof));
+
+        let buffer = [0x31, 0x05, 0x30, 0x00, 0x02, 0x01, 0x2A];
+        assert_eq!(
+            RequiredSet::deserialize(&mut &buffer[..]).unwrap(),
+            RequiredSet {
+                number: 42,
+                values: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn length_prefixed_custom_tag() {
+        let tag = Tag::new(3).application().constructed().into_tag_value();
+        let mut buffer = vec![];
+
+        let written = write_length_prefixed(tag, &mut buffer, |contents| {
+            5u8.serialize(&mut *contents)?;
+            vec![1u8, 2].serialize(&mut *contents)?;
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(written, buffer.len());
+        assert_eq!(
+            buffer,
+            [0x63, 0x0B, 0x02, 0x01, 0x05, 0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02]
+        );
+
+        let mut contents = read_contents(&mut &buffer[..], tag).unwrap();
+        assert_eq!(u8::deserialize(&mut contents).unwrap(), 5);
+        assert_eq!(Vec::<u8>::deserialize(&mut contents).unwrap(), [1, 2]);
+
+        // Empty body
+        buffer.clear();
+        assert_eq!(
+            write_length_prefixed(tag, &mut buffer, |_| Ok(())).unwrap(),
+            2
+        );
+        assert_eq!(buffer, [0x63, 0x00]);
+    }
+
+    #[test]
+    fn tag_universal_names() {
+        assert_eq!(Tag::new(INTEGER).universal_name(), Some("INTEGER"));
+        assert_eq!(Tag::new(SEQUENCE).universal_name(), Some("SEQUENCE"));
+        assert_eq!(
+            Tag::new(6).universal().primitive().universal_name(),
+            Some("OBJECT IDENTIFIER")
+        );
+        assert_eq!(Tag::new(2).context_specific().universal_name(), None);
+        assert_eq!(Tag::new(15).universal_name(), None);
+    }
+
+    #[test]
+    fn unexpected_tag_detail() {
+        // [0] EXPLICIT INTEGER where a bare INTEGER is expected
+        let buffer = [0xA0, 0x03, 0x02, 0x01, 0x05];
+        let e = u32::deserialize(&mut &buffer[..]).unwrap_err();
+
+        match e {
+            DerError::UnexpectedTag {
+                expected: Some(expected),
+                found,
+            } => {
+                assert_eq!(expected.into_tag_value(), INTEGER);
+                assert_eq!(found, Tag::new(0).context_specific().constructed());
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+
+        assert_eq!(
+            u32::deserialize(&mut &buffer[..]).unwrap_err().to_string(),
+            "unexpected tag: expected [UNIVERSAL 2], found [0] constructed"
+        );
+    }
+
+    #[test]
+    fn der_error_into_io_error() {
+        let tag_error = || DerError::UnexpectedTag {
+            expected: Some(Tag(INTEGER)),
+            found: Tag(NULL),
+        };
+        let e = io::Error::from(tag_error());
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), tag_error().to_string());
+
+        let inner = io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed");
+        let e = io::Error::from(DerError::Io(inner));
+        assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(e.to_string(), "pipe closed");
+
+        fn decode(bytes: &[u8]) -> io::Result<u8> {
+            Ok(u8::deserialize(&mut &bytes[..])?)
+        }
+
+        assert_eq!(decode(&[0x02, 0x01, 0x05]).unwrap(), 5);
+        assert_eq!(
+            decode(&[0x04, 0x01, 0x05]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn pre_encoded() {
+        let template: Vec<u32> = vec![1, 300, 70_000];
+        let pre_encoded = PreEncoded::new(&template);
+        assert_eq!(pre_encoded.as_bytes(), &template.to_der_vec()[..]);
+        assert_eq!(pre_encoded.encoded_len(), template.encoded_len());
+
+        // Written verbatim, every time
+        let mut buffer = vec![];
+        assert_eq!(pre_encoded.serialize(&mut buffer).unwrap(), 14);
+        assert_eq!(pre_encoded.serialize(&mut buffer).unwrap(), 14);
+        assert_eq!(&buffer[..14], pre_encoded.as_bytes());
+        assert_eq!(&buffer[14..], pre_encoded.as_bytes());
+
+        let mut bytes = &buffer[..];
+        let decoded = PreEncoded::deserialize(&mut bytes).unwrap();
+        assert_eq!(decoded, pre_encoded);
+        assert_eq!(bytes.len(), 14);
+        assert_eq!(decoded.decode::<Vec<u32>>().unwrap(), template);
+
+        assert!(PreEncoded::deserialize(&mut &[0x30, 0x03, 0x02][..]).is_err());
+    }
+
+    #[test]
+    fn base128_enc_dec() {
+        let cases: &[(u64, &[u8])] = &[
+            (0, &[0x00]),
+            (127, &[0x7F]),
+            (128, &[0x81, 0x00]),
+            (16383, &[0xFF, 0x7F]),
+            (16384, &[0x81, 0x80, 0x00]),
+            (
+                u64::MAX,
+                &[0x81, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F],
+            ),
+        ];
+
+        for (value, encoding) in cases {
+            let mut buffer = vec![];
+            assert_eq!(write_base128(*value, &mut buffer).unwrap(), encoding.len());
+            assert_eq!(&buffer[..], *encoding);
+
+            let mut bytes = *encoding;
+            assert_eq!(read_base128(&mut bytes).unwrap(), *value);
+            assert!(bytes.is_empty());
+        }
+
+        // Non-minimal leading group
+        assert!(matches!(
+            read_base128(&mut &[0x80, 0x01][..]),
+            Err(DerError::InvalidEncoding)
+        ));
+        // Continuation bit set on the final byte
+        assert!(matches!(
+            read_base128(&mut &[0x81][..]),
+            Err(DerError::UnexpectedEof)
+        ));
+        // Too large for a u64
+        assert!(matches!(
+            read_base128(&mut &[0x82, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00][..]),
+            Err(DerError::IntValueTooLarge)
+        ));
+    }
+
+    #[test]
+    fn high_tag_numbers() {
+        let class_and_form = Tag::new(0)
+            .context_specific()
+            .constructed()
+            .into_tag_value();
+        let mut buffer = vec![];
+
+        assert_eq!(
+            write_tag_number(class_and_form, 30, &mut buffer).unwrap(),
+            1
+        );
+        assert_eq!(buffer, [0xBE]);
+        buffer.clear();
+
+        assert_eq!(
+            write_tag_number(class_and_form, 31, &mut buffer).unwrap(),
+            2
+        );
+        assert_eq!(buffer, [0xBF, 0x1F]);
+        buffer.clear();
+
+        assert_eq!(
+            write_tag_number(class_and_form, 16384, &mut buffer).unwrap(),
+            4
+        );
+        assert_eq!(buffer, [0xBF, 0x81, 0x80, 0x00]);
+        assert_eq!(
+            read_tag_number(&mut &buffer[..]).unwrap(),
+            (class_and_form, 16384)
+        );
+
+        assert_eq!(read_tag_number(&mut &[0x02][..]).unwrap(), (0, 2));
+        // Low tag numbers may not use the high-tag-number form
+        assert!(read_tag_number(&mut &[0x1F, 0x02][..]).is_err());
+
+        // Whole TLVs with multi-byte tags can be captured
+        let buffer = [0x9F, 0x81, 0x00, 0x01, 0xAA, 0x05];
+        let mut bytes = &buffer[..];
+        let tlv = PreEncoded::deserialize(&mut bytes).unwrap();
+        assert_eq!(tlv.as_bytes(), &buffer[..5]);
+        assert_eq!(bytes, [0x05]);
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    struct Inner {
+        first: u8,
+        second: u8,
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    struct Outer(u32, Inner);
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    struct Attribute {
+        kind: ObjectIdentifier,
+        value: Utf8String,
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    struct Attributes {
+        version: u8,
+        attributes: Vec<Attribute>,
+    }
+
+    #[test]
+    fn sequence_of_derived() {
+        let buffer = [
+            0x30, 0x15, // SEQUENCE OF
+            0x30, 0x08, 0x06, 0x03, 0x55, 0x04, 0x03, 0x0C, 0x01, b'a', // 2.5.4.3 "a"
+            0x30, 0x09, 0x06, 0x03, 0x55, 0x04, 0x06, 0x0C, 0x02, b'U', b'S', // 2.5.4.6 "US"
+        ];
+        let attributes = Vec::<Attribute>::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(
+            attributes,
+            [
+                Attribute {
+                    kind: "2.5.4.3".parse().unwrap(),
+                    value: Utf8String::new("a").unwrap(),
+                },
+                Attribute {
+                    kind: "2.5.4.6".parse().unwrap(),
+                    value: Utf8String::new("US").unwrap(),
+                },
+            ]
+        );
+        assert_eq!(attributes.to_der_vec(), buffer);
+
+        // Nested inside another derived type
+        let outer = Attributes {
+            version: 1,
+            attributes,
+        };
+        let encoding = outer.to_der_vec();
+        assert_eq!(encoding[..5], [0x30, 0x1A, 0x02, 0x01, 0x01]);
+        assert_eq!(encoding[5..], buffer);
+        assert_eq!(Attributes::deserialize(&mut &encoding[..]).unwrap(), outer);
+
+        let empty = [0x30, 0x05, 0x02, 0x01, 0x00, 0x30, 0x00];
+        assert!(Attributes::deserialize(&mut &empty[..])
+            .unwrap()
+            .attributes
+            .is_empty());
+
+        // An element with a field missing
+        let buffer = [0x30, 0x07, 0x30, 0x05, 0x06, 0x03, 0x55, 0x04, 0x03];
+        let e = Vec::<Attribute>::deserialize(&mut &buffer[..]).unwrap_err();
+        assert!(matches!(e.root_cause(), DerError::UnexpectedEof));
+    }
+
+    // Declared in a different order to the SEQUENCE it encodes
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    struct Reordered {
+        #[der(order = 2)]
+        name: Utf8String,
+        #[der(order = 0)]
+        version: u8,
+        #[der(order = 1, default)]
+        critical: bool,
+    }
+
+    #[test]
+    fn field_order() {
+        let value = Reordered {
+            name: Utf8String::new("a").unwrap(),
+            version: 2,
+            critical: true,
+        };
+
+        let buffer = [
+            0x30, 0x09, 0x02, 0x01, 0x02, 0x01, 0x01, 0xFF, 0x0C, 0x01, b'a',
+        ];
+        assert_eq!(value.to_der_vec(), buffer);
+        assert_eq!(value.encoded_len(), buffer.len());
+        assert_eq!(Reordered::deserialize(&mut &buffer[..]).unwrap(), value);
+
+        // The declaration order isn't accepted
+        let buffer = [
+            0x30, 0x09, 0x0C, 0x01, b'a', 0x02, 0x01, 0x02, 0x01, 0x01, 0xFF,
+        ];
+        assert!(Reordered::deserialize(&mut &buffer[..]).is_err());
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    struct NullThenInt {
+        null: Null,
+        value: Option<u8>,
+    }
+
+    #[test]
+    fn definite_length_ignores_eoc() {
+        // Content starting with a NULL is just a NULL element
+        let buffer = [0x30, 0x04, 0x05, 0x00, 0x05, 0x00];
+        assert_eq!(
+            Vec::<Null>::deserialize(&mut &buffer[..]).unwrap(),
+            [Null, Null]
+        );
+        assert_eq!(
+            NullThenInt::deserialize(&mut &[0x30, 0x02, 0x05, 0x00][..]).unwrap(),
+            NullThenInt {
+                null: Null,
+                value: None
+            }
+        );
+
+        // `00 00` inside a definite length is an element, not a terminator
+        let buffer = [0x30, 0x04, 0x05, 0x00, 0x00, 0x00];
+        assert!(matches!(
+            Vec::<Null>::deserialize(&mut &buffer[..]),
+            Err(DerError::UnexpectedTag {
+                found: Tag(0x00),
+                ..
+            })
+        ));
+        assert!(matches!(
+            NullThenInt::deserialize(&mut &buffer[..])
+                .unwrap_err()
+                .root_cause(),
+            DerError::InvalidEncoding
+        ));
+
+        // The indefinite form itself is BER only
+        let buffer = [0x30, 0x80, 0x05, 0x00, 0x00, 0x00];
+        assert!(matches!(
+            Vec::<Null>::deserialize(&mut &buffer[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    enum Choice {
+        #[der(unknown)]
+        Unknown(AnyTlv),
+        Number(u32),
+        Text(Utf8String),
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    enum ClosedChoice {
+        Number(u32),
+        Flag(bool),
+    }
+
+    #[test]
+    fn choice_enums() {
+        let buffer = 7u32.to_der_vec();
+        let choice = Choice::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(choice, Choice::Number(7));
+        assert_eq!(choice.to_der_vec(), buffer);
+        assert_eq!(choice.encoded_len(), buffer.len());
+
+        let buffer = Utf8String::new("hi").unwrap().to_der_vec();
+        assert!(matches!(
+            Choice::deserialize(&mut &buffer[..]).unwrap(),
+            Choice::Text(_)
+        ));
+
+        // An alternative this version doesn't know about
+        let buffer = [0x04, 0x02, 0xAB, 0xCD];
+        let choice = Choice::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(
+            choice,
+            Choice::Unknown(AnyTlv::new(OCTET_STRING, &[0xAB, 0xCD]))
+        );
+        assert_eq!(choice.to_der_vec(), buffer);
+        assert!(Choice::matches_tag(OCTET_STRING));
+
+        assert_eq!(
+            ClosedChoice::deserialize(&mut &[0x01, 0x01, 0xFF][..]).unwrap(),
+            ClosedChoice::Flag(true)
+        );
+        assert!(!ClosedChoice::matches_tag(OCTET_STRING));
+        let e = ClosedChoice::deserialize(&mut &buffer[..]).unwrap_err();
+        assert_eq!(e.to_string(), "unexpected tag [UNIVERSAL 4]");
+
+        // Errors inside an alternative name it
+        let e = ClosedChoice::deserialize(&mut &[0x01, 0x01, 0x01][..]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "while decoding ClosedChoice::Flag: invalid DER encoding"
+        );
+    }
+
+    // `Version ::= INTEGER { v1(0), v2(1), v3(2) }`
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    #[der(integer)]
+    enum Version {
+        V1,
+        V2,
+        V3,
+    }
+
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    #[der(integer)]
+    enum Status {
+        Revoked = -1,
+        Good = 200,
+    }
+
+    #[test]
+    fn integer_enums() {
+        for (version, value) in [(Version::V1, 0u8), (Version::V2, 1), (Version::V3, 2)] {
+            let buffer = version.to_der_vec();
+            assert_eq!(buffer, value.to_der_vec());
+            assert_eq!(Version::deserialize(&mut &buffer[..]).unwrap(), version);
+        }
+
+        assert!(matches!(
+            Version::deserialize(&mut &[0x02, 0x01, 0x03][..]),
+            Err(DerError::InvalidEncoding)
+        ));
+
+        assert_eq!(Status::Good.to_der_vec(), [0x02, 0x02, 0x00, 0xC8]);
+        assert_eq!(
+            Status::deserialize(&mut &[0x02, 0x01, 0xFF][..]).unwrap(),
+            Status::Revoked
+        );
+        assert!(Status::deserialize(&mut &[0x02, 0x01, 0x00][..]).is_err());
+    }
+
+    crate::der_struct! {
+        #[derive(Debug, PartialEq)]
+        AlgorithmIdentifier {
+            algorithm: ObjectIdentifier,
+            parameters: Option<Null>,
+        }
+    }
+
+    crate::der_struct! {
+        #[derive(Debug, PartialEq)]
+        SubjectPublicKeyInfo {
+            algorithm: AlgorithmIdentifier,
+            subject_public_key: BitString,
+        }
+    }
+
+    crate::der_struct! {
+        Flagged {
+            #[der(default)]
+            flag: bool,
+        }
+    }
+
+    #[test]
+    fn der_struct_macro() {
+        let info = SubjectPublicKeyInfo {
+            algorithm: AlgorithmIdentifier {
+                algorithm: ObjectIdentifier::from_bytes(oids::ED25519).unwrap(),
+                parameters: None,
+            },
+            subject_public_key: BitString::from_bytes(vec![0xAB; 32]),
+        };
+
+        let buffer = info.to_der_vec();
+        assert_eq!(
+            buffer[..12],
+            [0x30, 0x2A, 0x30, 0x05, 0x06, 0x03, 0x2B, 0x65, 0x70, 0x03, 0x21, 0x00]
+        );
+        assert_eq!(buffer.len(), 44);
+        assert_eq!(
+            SubjectPublicKeyInfo::deserialize(&mut &buffer[..]).unwrap(),
+            info
+        );
+
+        assert_eq!(Flagged { flag: false }.to_der_vec(), [0x30, 0x00]);
+        assert!(
+            Flagged::deserialize(&mut &[0x30, 0x03, 0x01, 0x01, 0xFF][..])
+                .unwrap()
+                .flag
+        );
+    }
+
+    #[test]
+    fn error_context() {
+        let e = context::<(), _>(Err(DerError::InvalidEncoding), || {
+            "while parsing TBSCertificate.serialNumber"
+        })
+        .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "while parsing TBSCertificate.serialNumber: invalid DER encoding"
+        );
+        assert!(matches!(e.root_cause(), DerError::InvalidEncoding));
+        assert!(context(Ok(5), || unreachable!()).is_ok());
+
+        // The derive annotates each field
+        let buffer = [
+            0x30, 0x0A, 0x02, 0x01, 0x01, 0x30, 0x05, 0x02, 0x01, 0x02, 0x04, 0x00,
+        ];
+        let e = Outer::deserialize(&mut &buffer[..]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "while decoding Outer.1: while decoding Inner.second: \
+             unexpected tag: expected [UNIVERSAL 2], found [UNIVERSAL 4]"
+        );
+        assert!(matches!(e.root_cause(), DerError::UnexpectedTag { .. }));
+        assert_eq!(io::Error::from(e).kind(), io::ErrorKind::InvalidData);
+
+        let buffer = [
+            0x30, 0x0B, 0x02, 0x01, 0x01, 0x30, 0x06, 0x02, 0x01, 0x02, 0x02, 0x01, 0x03,
+        ];
+        assert_eq!(
+            Outer::deserialize(&mut &buffer[..]).unwrap(),
+            Outer(
+                1,
+                Inner {
+                    first: 2,
+                    second: 3
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn to_der_vec_single_allocation() {
+        let large: Vec<Vec<u64>> = (0..1000).map(|i| vec![i, rand::random()]).collect();
+        let buffer = large.to_der_vec();
+        assert_eq!(buffer.capacity(), buffer.len());
+        assert_eq!(
+            Vec::<Vec<u64>>::deserialize(&mut &buffer[..]).unwrap(),
+            large
+        );
+
+        let buffer = large.to_der_vec_with_capacity(16);
+        assert_eq!(buffer, large.to_der_vec());
+    }
+
+    #[test]
+    fn big_endian_reads() {
+        assert_eq!(read_be_int(&[0x80]).unwrap(), -128);
+        assert_eq!(read_be_int(&[0x00, 0xFF]).unwrap(), 255);
+        assert_eq!(read_be_int(&i128::MIN.to_be_bytes()).unwrap(), i128::MIN);
+        assert!(matches!(
+            read_be_int(&[0x01; 17]),
+            Err(DerError::IntValueTooLarge)
+        ));
+
+        assert_eq!(read_be_uint(&[0x00]).unwrap(), 0);
+        assert_eq!(read_be_uint(&[0x00, 0x80]).unwrap(), 128);
+        let mut max = vec![0x00];
+        max.extend_from_slice(&u128::MAX.to_be_bytes());
+        assert_eq!(read_be_uint(&max).unwrap(), u128::MAX);
+        assert!(matches!(
+            read_be_uint(&[0xFF]),
+            Err(DerError::IntValueTooLarge)
+        ));
+
+        let mut bytes = &[0x2A][..];
+        assert_eq!(read_u8(&mut bytes).unwrap(), 0x2A);
+        assert!(matches!(read_u8(&mut bytes), Err(DerError::UnexpectedEof)));
+
+        // Long form lengths, including ones too large for a u32
+        let mut bytes = &[0x82, 0x01, 0x00][..];
+        assert_eq!(Length::deserialize(&mut bytes).unwrap().into_usize(), 256);
+        assert!(bytes.is_empty());
+        assert!(Length::deserialize(&mut &[0x85, 0x01, 0x00, 0x00, 0x00, 0x00][..]).is_err());
+        assert!(matches!(
+            Length::deserialize(&mut &[0x82, 0x01][..]),
+            Err(DerError::UnexpectedEof)
+        ));
+
+        for value in [i64::MIN, -1, 0, 1, i64::MAX] {
+            assert_eq!(
+                i64::deserialize(&mut &value.to_der_vec()[..]).unwrap(),
+                value
+            );
+        }
+        for value in [0, 0x7F, 0x80, u64::MAX] {
+            assert_eq!(
+                u64::deserialize(&mut &value.to_der_vec()[..]).unwrap(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn signed_minimal_forms() {
+        let cases: [(i32, &[u8]); 6] = [
+            (0, &[0x02, 0x01, 0x00]),
+            (-1, &[0x02, 0x01, 0xFF]),
+            (127, &[0x02, 0x01, 0x7F]),
+            (128, &[0x02, 0x02, 0x00, 0x80]),
+            (-128, &[0x02, 0x01, 0x80]),
+            (-129, &[0x02, 0x02, 0xFF, 0x7F]),
+        ];
+
+        for (value, encoding) in cases {
+            assert_eq!(value.to_der_vec(), encoding, "{}i32", value);
+            assert_eq!((value as i16).to_der_vec(), encoding, "{}i16", value);
+            assert_eq!((value as i64).to_der_vec(), encoding, "{}i64", value);
+            assert_eq!(value.encoded_len(), encoding.len());
+
+            assert_eq!(i32::deserialize(&mut &encoding[..]).unwrap(), value);
+            assert_eq!(i16::deserialize(&mut &encoding[..]).unwrap(), value as i16);
+        }
+
+        assert_eq!(i16::MIN.to_der_vec(), [0x02, 0x02, 0x80, 0x00]);
+        assert_eq!(i16::MAX.to_der_vec(), [0x02, 0x02, 0x7F, 0xFF]);
+        assert_eq!(i32::MIN.to_der_vec(), [0x02, 0x04, 0x80, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn integer_minimality() {
+        // Redundant leading 0xFF
+        assert!(matches!(
+            i32::deserialize(&mut &[0x02, 0x02, 0xFF, 0x80][..]),
+            Err(DerError::InvalidEncoding)
+        ));
+        assert_eq!(
+            i32::deserialize(&mut &[0x02, 0x01, 0x80][..]).unwrap(),
+            -128
+        );
+        assert_eq!(
+            i32::deserialize(&mut &[0x02, 0x02, 0xFF, 0x7F][..]).unwrap(),
+            -129
+        );
+
+        // Redundant leading 0x00
+        assert!(matches!(
+            u32::deserialize(&mut &[0x02, 0x02, 0x00, 0x05][..]),
+            Err(DerError::InvalidEncoding)
+        ));
+        assert_eq!(
+            u32::deserialize(&mut &[0x02, 0x02, 0x00, 0x80][..]).unwrap(),
+            128
+        );
+
+        // No content
+        assert!(matches!(
+            u32::deserialize(&mut &[0x02, 0x00][..]),
+            Err(DerError::InvalidEncoding)
+        ));
+
+        // Unsigned values with the top bit set keep their sign pad
+        assert_eq!(200u8.to_der_vec(), [0x02, 0x02, 0x00, 0xC8]);
+        assert_eq!(
+            u8::deserialize(&mut &[0x02, 0x02, 0x00, 0xC8][..]).unwrap(),
+            200
+        );
+        assert!(matches!(
+            u8::deserialize(&mut &[0x02, 0x01, 0xC8][..]),
+            Err(DerError::IntValueTooLarge)
+        ));
+        assert_eq!((-56i8).to_der_vec(), [0x02, 0x01, 0xC8]);
+
+        assert_eq!(
+            u128::MAX.to_der_vec(),
+            [
+                0x02, 0x11, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0xFF, 0xFF, 0xFF, 0xFF, 0xFF
+            ]
+        );
+        assert_eq!(
+            u128::deserialize(&mut &u128::MAX.to_der_vec()[..]).unwrap(),
+            u128::MAX
+        );
+    }
+
+    #[test]
+    fn serialize_into_appends() {
+        // Reserved header space
+        let mut buffer = vec![0xAA, 0xBB];
+
+        assert_eq!(5u8.serialize_into(&mut buffer).unwrap(), 3);
+        assert_eq!(vec![1u16, 2].serialize_into(&mut buffer).unwrap(), 8);
+        assert_eq!(
+            buffer,
+            [0xAA, 0xBB, 0x02, 0x01, 0x05, 0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02]
+        );
+
+        let mut bytes = &buffer[2..];
+        assert_eq!(u8::deserialize(&mut bytes).unwrap(), 5);
+        assert_eq!(Vec::<u16>::deserialize(&mut bytes).unwrap(), [1, 2]);
+        assert!(bytes.is_empty());
+    }
+
+    // Composes nested serializers by passing the same `&mut W` along
+    struct ManualPair(u8, Vec<u16>);
+
+    impl DerSerialize for ManualPair {
+        fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+            let content_len = self.0.encoded_len() + self.1.encoded_len();
+            let mut written = write_header(SEQUENCE, content_len, writer)?;
+            written += self.0.serialize(writer)?;
+            written += self.1.serialize(writer)?;
+
+            Ok(written)
+        }
+    }
+
+    #[derive(DerSerialize)]
+    struct DerivedPair(u8, Vec<u16>);
+
+    #[test]
+    fn nested_serialization() {
+        let manual = ManualPair(5, vec![1, 2]);
+        let derived = DerivedPair(5, vec![1, 2]);
+        assert_eq!(manual.to_der_vec(), derived.to_der_vec());
+
+        // Unsized writers work too
+        let mut buffer = Vec::new();
+        let writer: &mut dyn Write = &mut buffer;
+        assert_eq!(derived.serialize(writer).unwrap(), 13);
+        assert_eq!(buffer, manual.to_der_vec());
+
+        let mut array = [0u8; 13];
+        manual.serialize(&mut &mut array[..]).unwrap();
+        assert_eq!(array[..], buffer[..]);
+    }
+
+    #[test]
+    fn skipping_tlvs() {
+        let buffer = [0x02, 0x01, 0x05, 0x05, 0x00];
+        let mut bytes = &buffer[..];
+        assert_eq!(skip_tlv(&mut bytes).unwrap(), 3);
+        assert_eq!(bytes, [0x05, 0x00]);
+
+        // Nested definite length, then indefinite length with a nested
+        // indefinite length element
+        let buffer = [
+            0x30, 0x05, 0x30, 0x03, 0x02, 0x01, 0x01, 0x30, 0x80, 0x02, 0x01, 0x02, 0xA0, 0x80,
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0xFF,
+        ];
+        let mut bytes = &buffer[..];
+        assert_eq!(skip_tlv(&mut bytes).unwrap(), 7);
+        assert_eq!(skip_tlv(&mut bytes).unwrap(), 13);
+        assert!(bool::deserialize(&mut bytes).unwrap());
+
+        // High tag number identifier
+        let mut bytes = &[0x9F, 0x64, 0x01, 0xAA][..];
+        assert_eq!(skip_tlv(&mut bytes).unwrap(), 4);
+        assert!(bytes.is_empty());
+
+        // Missing EOC, truncated content, indefinite primitive
+        assert!(skip_tlv(&mut &[0x30, 0x80, 0x05, 0x00][..]).is_err());
+        assert!(skip_tlv(&mut &[0x04, 0x03, 0x01][..]).is_err());
+        assert!(skip_tlv(&mut &[0x04, 0x80, 0x00, 0x00][..]).is_err());
+    }
+
+    #[test]
+    fn skipping_tlvs_nesting_limit() {
+        fn nested_indefinite(depth: usize) -> Vec<u8> {
+            let mut buffer = [0x30, 0x80].repeat(depth);
+            buffer.resize(depth * 4, 0x00);
+            buffer
+        }
+
+        let buffer = nested_indefinite(MAX_DEPTH);
+        assert_eq!(skip_tlv(&mut &buffer[..]).unwrap(), buffer.len());
+
+        for depth in [MAX_DEPTH + 1, 500_000] {
+            let buffer = nested_indefinite(depth);
+            assert!(matches!(
+                skip_tlv(&mut &buffer[..]),
+                Err(DerError::InvalidEncoding)
+            ));
+        }
+    }
+
+    #[test]
+    fn reused_buffer() {
+        let mut buffer = Vec::new();
+        let value = vec![1u32, 70_000, 3];
+
+        assert_eq!(encode_into_reused(&value, &mut buffer).unwrap(), 13);
+        assert_eq!(buffer, value.to_der_vec());
+        let (ptr, capacity) = (buffer.as_ptr(), buffer.capacity());
+
+        // Same size and smaller encodings reuse the allocation
+        for _ in 0..3 {
+            encode_into_reused(&value, &mut buffer).unwrap();
+            assert_eq!(buffer, value.to_der_vec());
+        }
+        encode_into_reused(&5u8, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x02, 0x01, 0x05]);
+
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert_eq!(buffer.capacity(), capacity);
+    }
+
+    #[test]
+    fn tag_number_validation() {
+        assert_eq!(Tag::try_new(2), Some(Tag::new(2)));
+        assert_eq!(Tag::try_new(63), Some(Tag::new(63)));
+        assert_eq!(Tag::try_new(64), None);
+        assert_eq!(Tag::try_new(0xFF), None);
+
+        assert_eq!(
+            Tag::new(0x10).constructed().universal().into_tag_value(),
+            SEQUENCE
+        );
+        assert_eq!(
+            Tag::new(3).private().primitive().into_tag_value(),
+            0b1100_0011
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "tag number must be below 64")]
+    fn tag_number_out_of_range() {
+        let tag_number = std::hint::black_box(0xFF);
+        Tag::new(tag_number).universal();
+    }
+
+    #[test]
+    fn serialize_trait_objects() {
+        let values: Vec<Box<dyn ErasedDerSerialize>> = vec![
+            Box::new(5u8),
+            Box::new(Null),
+            Box::new(vec![1u16, 2]),
+            Box::new("2.5.4.3".parse::<ObjectIdentifier>().unwrap()),
+        ];
+
+        let buffer = values.to_der_vec();
+        assert_eq!(
+            buffer,
+            [
+                0x30, 0x12, 0x02, 0x01, 0x05, 0x05, 0x00, 0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01,
+                0x02, 0x06, 0x03, 0x55, 0x04, 0x03
+            ]
+        );
+        assert_eq!(der_size(&values), buffer.len());
+
+        let mut contents = read_contents(&mut &buffer[..], SEQUENCE).unwrap();
+        assert_eq!(u8::deserialize(&mut contents).unwrap(), 5);
+        assert_eq!(Null::deserialize(&mut contents).unwrap(), Null);
+        assert_eq!(Vec::<u16>::deserialize(&mut contents).unwrap(), [1, 2]);
+        assert_eq!(
+            ObjectIdentifier::deserialize(&mut contents)
+                .unwrap()
+                .to_string(),
+            "2.5.4.3"
+        );
+    }
+
+    #[test]
+    fn bounded_sequence_of() {
+        let buffer = vec![1u8, 2, 3].to_der_vec();
+
+        assert!(matches!(
+            read_sequence_of_bounded::<u8>(&mut &buffer[..], 2),
+            Err(DerError::TooManyElements { max: 2 })
+        ));
+        assert_eq!(
+            read_sequence_of_bounded::<u8>(&mut &buffer[..], 3).unwrap(),
+            [1, 2, 3]
+        );
+        assert_eq!(
+            read_sequence_of_bounded::<u8>(&mut &[0x30, 0x00][..], 0).unwrap(),
+            []
+        );
+    }
+
+    #[test]
+    fn lenient_sequence_of() {
+        // 1, a non-minimal INTEGER, then 3
+        let buffer = [
+            0x30, 0x0A, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x02, 0x02, 0x01, 0x03,
+        ];
+        let (elements, errors) = decode_sequence_of_lenient::<u8>(&buffer);
+        assert_eq!(elements, [1, 3]);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], DerError::InvalidEncoding));
+
+        // Truncated element header stops decoding
+        let buffer = [0x30, 0x05, 0x02, 0x01, 0x01, 0x02, 0x05];
+        let (elements, errors) = decode_sequence_of_lenient::<u8>(&buffer);
+        assert_eq!(elements, [1]);
+        assert!(matches!(errors[..], [DerError::UnexpectedEof]));
+
+        let (elements, errors) = decode_sequence_of_lenient::<u8>(&[0x31, 0x00]);
+        assert!(elements.is_empty());
+        assert!(matches!(errors[..], [DerError::UnexpectedTag { .. }]));
+    }
+
+    fn assert_value_type<T: Clone + std::fmt::Debug + Eq + std::hash::Hash>(value: T) {
+        let cloned = value.clone();
+        assert_eq!(cloned, value);
+        assert!(!format!("{:?}", cloned).is_empty());
+    }
+
+    #[test]
+    fn value_types_are_cloneable() {
+        assert_value_type(Length::new(5));
+        assert_value_type(Tag::new(2).universal());
+        assert_value_type(Null);
+        assert_value_type(OctetString::new(vec![1, 2]));
+        assert_value_type(BitString::new(vec![0x80], 7).unwrap());
+        assert_value_type("1.2.840.113549".parse::<ObjectIdentifier>().unwrap());
+        assert_value_type(GeneralizedTime::new(2021, 1, 1, 0, 0, 0).unwrap());
+        assert_value_type(Utf8String::new("utf8").unwrap());
+        assert_value_type(PrintableString::new("printable").unwrap());
+        assert_value_type(Ia5String::new("ia5").unwrap());
+        assert_value_type(AnyTlv::new(NULL, &[]));
+        assert_value_type(PreEncoded::new(&5u8));
+        assert_value_type(SetOf::new(vec![2u8, 1]));
+        assert_value_type(Explicit::<0, _>(5u8));
+        assert_value_type(x509::RdnSequence(vec![]));
+    }
 }
@@ -0,0 +1,45 @@
+use crate::{read_contents, write_header, DerDeserialize, DerError, DerSerialize, NULL};
+use std::io::{self, Write};
+
+/// The NULL value, encoded as `05 00`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Null;
+
+impl DerSerialize for Null {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        write_header(NULL, 0, writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        2
+    }
+}
+
+impl DerDeserialize for Null {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        if !read_contents(bytes, NULL)?.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(Null)
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == NULL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn null_enc_dec() {
+        assert_eq!(Null.to_der_vec(), [0x05, 0x00]);
+        assert_eq!(Null::deserialize(&mut &[0x05, 0x00][..]).unwrap(), Null);
+        assert!(matches!(
+            Null::deserialize(&mut &[0x05, 0x01, 0x00][..]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{
+    read_contents, tlv_len, write_header, DerDeserialize, DerError, DerSerialize,
+    DerSerializeContent, OCTET_STRING,
+};
+use std::io::{self, Write};
+
+/// An OCTET STRING.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OctetString(Vec<u8>);
+
+impl OctetString {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        OctetString(bytes.into())
+    }
+
+    /// Wraps the DER encoding of `value`, as used for X.509 extension values.
+    pub fn from_der<T: DerSerialize>(value: &T) -> Self {
+        OctetString(value.to_der_vec())
+    }
+
+    /// Decodes the content as the DER encoding of a single `T`.
+    pub fn inner_der<T: DerDeserialize>(&self) -> Result<T, DerError> {
+        let mut bytes = &self.0[..];
+        let value = T::deserialize(&mut bytes)?;
+
+        if !bytes.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(value)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<Vec<u8>> for OctetString {
+    fn from(bytes: Vec<u8>) -> Self {
+        OctetString(bytes)
+    }
+}
+
+impl DerSerializeContent for OctetString {
+    fn serialize_content<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        writer.write_all(&self.0)?;
+
+        Ok(self.0.len())
+    }
+}
+
+impl DerSerialize for OctetString {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let written = write_header(OCTET_STRING, self.0.len(), writer)?;
+        writer.write_all(&self.0)?;
+
+        Ok(written + self.0.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.0.len())
+    }
+}
+
+impl DerDeserialize for OctetString {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        Ok(OctetString(read_contents(bytes, OCTET_STRING)?.to_vec()))
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == OCTET_STRING
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn octet_string_enc_dec() {
+        let octets = OctetString::new(vec![0xDE, 0xAD]);
+        let buffer = octets.to_der_vec();
+        assert_eq!(buffer, [0x04, 0x02, 0xDE, 0xAD]);
+        assert_eq!(OctetString::deserialize(&mut &buffer[..]).unwrap(), octets);
+
+        let mut content = Vec::new();
+        octets.serialize_content(&mut content).unwrap();
+        assert_eq!(content, [0xDE, 0xAD]);
+        assert_eq!(octets.into_inner(), [0xDE, 0xAD]);
+
+        let empty = OctetString::default();
+        assert_eq!(empty.to_der_vec(), [0x04, 0x00]);
+    }
+
+    #[test]
+    fn der_in_octet_string() {
+        let octets = OctetString::from_der(&300u32);
+        assert_eq!(octets.as_bytes(), [0x02, 0x02, 0x01, 0x2C]);
+
+        let buffer = octets.to_der_vec();
+        assert_eq!(buffer, [0x04, 0x04, 0x02, 0x02, 0x01, 0x2C]);
+
+        let decoded = OctetString::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(decoded.inner_der::<u32>().unwrap(), 300);
+        assert!(decoded.inner_der::<Vec<u32>>().is_err());
+
+        // Trailing bytes after the nested value
+        let octets = OctetString::new(vec![0x02, 0x01, 0x05, 0x00]);
+        assert!(matches!(
+            octets.inner_der::<u32>(),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+}
@@ -0,0 +1,491 @@
+use crate::{
+    read_base128, read_contents, tlv_len, write_base128, write_header, DerDeserialize, DerError,
+    DerSerialize, OBJECT_IDENTIFIER,
+};
+use std::{
+    fmt,
+    io::{self, Write},
+    marker::PhantomData,
+    str::FromStr,
+};
+
+/// An OBJECT IDENTIFIER, stored as its DER content bytes.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectIdentifier(Vec<u8>);
+
+impl ObjectIdentifier {
+    /// Builds an OID from its arcs. There must be at least two arcs, the first
+    /// at most 2, and the second below 40 unless the first is 2.
+    pub fn from_arcs(arcs: &[u64]) -> Result<Self, DerError> {
+        let (first, second, rest) = match arcs {
+            [first, second, rest @ ..] => (*first, *second, rest),
+            _ => return Err(DerError::InvalidEncoding),
+        };
+
+        let combined = match first {
+            0 | 1 if second < 40 => first * 40 + second,
+            2 => second.checked_add(80).ok_or(DerError::IntValueTooLarge)?,
+            _ => return Err(DerError::InvalidEncoding),
+        };
+
+        let mut bytes = Vec::new();
+        write_base128(combined, &mut bytes)?;
+
+        for arc in rest {
+            write_base128(*arc, &mut bytes)?;
+        }
+
+        Ok(ObjectIdentifier(bytes))
+    }
+
+    /// Validates DER content bytes as an OID: minimal base 128
+    /// subidentifiers, the last one complete. Arcs may be of any size.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DerError> {
+        if bytes.last().is_none_or(|last| last & 0x80 != 0) {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        // A subidentifier starts wherever the byte before it ends one
+        let mut starts_subidentifier = true;
+        for &byte in bytes {
+            if starts_subidentifier && byte == 0x80 {
+                return Err(DerError::InvalidEncoding);
+            }
+
+            starts_subidentifier = byte & 0x80 == 0;
+        }
+
+        Ok(ObjectIdentifier(bytes.to_vec()))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Fails with `DerError::IntValueTooLarge` if an arc doesn't fit in a
+    /// `u64`, such as a UUID under `2.25`. `Display` handles any size.
+    pub fn arcs(&self) -> Result<Vec<u64>, DerError> {
+        arcs(&self.0)
+    }
+
+    // The base 128 subidentifiers, each ending with a byte below 0x80
+    fn subidentifiers(&self) -> impl Iterator<Item = &[u8]> {
+        self.0.split_inclusive(|byte| byte & 0x80 == 0)
+    }
+}
+
+/// The DER content bytes of an OID, computed at compile time:
+/// `oid!(2, 16, 840, 1, 101, 3, 4, 2, 1)` is a `&'static [u8]`. Invalid
+/// arcs fail to compile.
+#[macro_export]
+macro_rules! oid {
+    ($($arc:expr),+ $(,)?) => {{
+        const ARCS: &[u64] = &[$($arc),+];
+        const BYTES: [u8; $crate::__private::oid_len(ARCS)] = $crate::__private::oid_bytes(ARCS);
+        &BYTES as &'static [u8]
+    }};
+}
+
+// The first two arcs share a subidentifier
+const fn first_subidentifier(arcs: &[u64]) -> u64 {
+    assert!(arcs.len() >= 2, "an OID needs at least two arcs");
+
+    match arcs[0] {
+        0 | 1 => {
+            assert!(arcs[1] < 40, "the second OID arc must be below 40");
+            arcs[0] * 40 + arcs[1]
+        }
+        2 => arcs[1] + 80,
+        _ => panic!("the first OID arc must be 0, 1 or 2"),
+    }
+}
+
+const fn base128_len(value: u64) -> usize {
+    match 64 - value.leading_zeros() as usize {
+        0 => 1,
+        bits => bits.div_ceil(7),
+    }
+}
+
+#[doc(hidden)]
+pub const fn oid_len(arcs: &[u64]) -> usize {
+    let mut len = base128_len(first_subidentifier(arcs));
+    let mut i = 2;
+
+    while i < arcs.len() {
+        len += base128_len(arcs[i]);
+        i += 1;
+    }
+
+    len
+}
+
+#[doc(hidden)]
+pub const fn oid_bytes<const LEN: usize>(arcs: &[u64]) -> [u8; LEN] {
+    let mut bytes = [0u8; LEN];
+    let mut pos = 0;
+    let mut i = 1;
+
+    while i < arcs.len() {
+        let value = if i == 1 {
+            first_subidentifier(arcs)
+        } else {
+            arcs[i]
+        };
+
+        let mut group = base128_len(value);
+        while group > 0 {
+            group -= 1;
+            let continuation = if group == 0 { 0 } else { 0x80 };
+            bytes[pos] = (value >> (group * 7)) as u8 & 0x7F | continuation;
+            pos += 1;
+        }
+
+        i += 1;
+    }
+
+    assert!(pos == LEN, "OID length doesn't match its arcs");
+    bytes
+}
+
+fn arcs(mut bytes: &[u8]) -> Result<Vec<u64>, DerError> {
+    let mut arcs = Vec::new();
+
+    while !bytes.is_empty() {
+        let subidentifier = read_base128(&mut bytes)?;
+
+        if arcs.is_empty() {
+            match subidentifier {
+                0..=39 => arcs.extend_from_slice(&[0, subidentifier]),
+                40..=79 => arcs.extend_from_slice(&[1, subidentifier - 40]),
+                _ => arcs.extend_from_slice(&[2, subidentifier - 80]),
+            }
+        } else {
+            arcs.push(subidentifier);
+        }
+    }
+
+    Ok(arcs)
+}
+
+// Base 10^9 digits, least significant first, so that arcs of any size can
+// be printed
+const DECIMAL_LIMB: u64 = 1_000_000_000;
+
+fn decimal_limbs(subidentifier: &[u8]) -> Vec<u64> {
+    let mut limbs = vec![0];
+
+    for byte in subidentifier {
+        let mut carry = u64::from(byte & 0x7F);
+
+        for limb in &mut limbs {
+            let value = *limb * 128 + carry;
+            *limb = value % DECIMAL_LIMB;
+            carry = value / DECIMAL_LIMB;
+        }
+
+        if carry != 0 {
+            limbs.push(carry);
+        }
+    }
+
+    limbs
+}
+
+// `limbs` must be at least `n`
+fn subtract_limbs(limbs: &mut Vec<u64>, n: u64) {
+    let mut borrow = n;
+
+    for limb in limbs.iter_mut() {
+        if *limb >= borrow {
+            *limb -= borrow;
+            break;
+        }
+
+        *limb += DECIMAL_LIMB - borrow;
+        borrow = 1;
+    }
+
+    while limbs.len() > 1 && limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+}
+
+impl fmt::Display for ObjectIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, subidentifier) in self.subidentifiers().enumerate() {
+            let mut limbs = decimal_limbs(subidentifier);
+
+            if i == 0 {
+                // The first two arcs share a subidentifier
+                let first = match limbs[..] {
+                    [n] if n < 80 => n / 40,
+                    _ => 2,
+                };
+                subtract_limbs(&mut limbs, first * 40);
+                write!(f, "{}", first)?;
+            }
+
+            let (most_significant, rest) = limbs.split_last().expect("limbs is never empty");
+            write!(f, ".{}", most_significant)?;
+
+            for limb in rest.iter().rev() {
+                write!(f, "{:09}", limb)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for ObjectIdentifier {
+    type Err = DerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let arcs = s
+            .split('.')
+            .map(|arc| {
+                // `u64::from_str` would also accept a leading `+`
+                if !arc.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(DerError::InvalidEncoding);
+                }
+
+                arc.parse().map_err(|_| DerError::InvalidEncoding)
+            })
+            .collect::<Result<Vec<u64>, _>>()?;
+
+        ObjectIdentifier::from_arcs(&arcs)
+    }
+}
+
+impl DerSerialize for ObjectIdentifier {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let written = write_header(OBJECT_IDENTIFIER, self.0.len(), writer)?;
+        writer.write_all(&self.0)?;
+
+        Ok(written + self.0.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.0.len())
+    }
+}
+
+impl DerDeserialize for ObjectIdentifier {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        ObjectIdentifier::from_bytes(read_contents(bytes, OBJECT_IDENTIFIER)?)
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == OBJECT_IDENTIFIER
+    }
+}
+
+/// A marker type naming a single OBJECT IDENTIFIER, for use with `FixedOid`.
+pub trait KnownOid {
+    /// The DER content bytes, as produced by `oid!`.
+    const OID: &'static [u8];
+}
+
+/// An OBJECT IDENTIFIER constrained to the single value `O::OID`. Encoding
+/// always writes that OID; decoding anything else is an error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FixedOid<O: KnownOid>(PhantomData<O>);
+
+impl<O: KnownOid> FixedOid<O> {
+    pub fn new() -> Self {
+        FixedOid(PhantomData)
+    }
+
+    pub fn as_bytes(&self) -> &'static [u8] {
+        O::OID
+    }
+}
+
+impl<O: KnownOid> DerSerialize for FixedOid<O> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let written = write_header(OBJECT_IDENTIFIER, O::OID.len(), writer)?;
+        writer.write_all(O::OID)?;
+
+        Ok(written + O::OID.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(O::OID.len())
+    }
+}
+
+impl<O: KnownOid> DerDeserialize for FixedOid<O> {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        if read_contents(bytes, OBJECT_IDENTIFIER)? != O::OID {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(FixedOid::new())
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == OBJECT_IDENTIFIER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oid_enc_dec() {
+        // sha256WithRSAEncryption
+        let oid: ObjectIdentifier = "1.2.840.113549.1.1.11".parse().unwrap();
+        let encoding = [
+            0x06, 0x09, 0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B,
+        ];
+
+        assert_eq!(oid.to_der_vec(), encoding);
+        assert_eq!(oid.arcs().unwrap(), [1, 2, 840, 113_549, 1, 1, 11]);
+        assert_eq!(oid.to_string(), "1.2.840.113549.1.1.11");
+        assert_eq!(
+            ObjectIdentifier::deserialize(&mut &encoding[..]).unwrap(),
+            oid
+        );
+
+        assert!("1.40".parse::<ObjectIdentifier>().is_err());
+        assert!("3.1".parse::<ObjectIdentifier>().is_err());
+        assert!("1.2.a".parse::<ObjectIdentifier>().is_err());
+        assert!("1..2".parse::<ObjectIdentifier>().is_err());
+    }
+
+    #[test]
+    fn oid_large_second_arc() {
+        let oid: ObjectIdentifier = "2.999.3".parse().unwrap();
+        assert_eq!(oid.as_bytes(), [0x88, 0x37, 0x03]);
+        assert_eq!(oid.arcs().unwrap(), [2, 999, 3]);
+        assert_eq!(oid.to_string(), "2.999.3");
+        assert_eq!(oid.as_bytes(), oid!(2, 999, 3));
+
+        let oid: ObjectIdentifier = "2.100.3".parse().unwrap();
+        assert_eq!(oid.as_bytes(), [0x81, 0x34, 0x03]);
+        assert_eq!(
+            ObjectIdentifier::from_bytes(&[0x81, 0x34, 0x03])
+                .unwrap()
+                .arcs()
+                .unwrap(),
+            [2, 100, 3]
+        );
+
+        // The first subidentifier alone
+        assert_eq!(
+            ObjectIdentifier::from_bytes(&[0x50])
+                .unwrap()
+                .arcs()
+                .unwrap(),
+            [2, 0]
+        );
+        assert_eq!(
+            ObjectIdentifier::from_bytes(&[0x4F])
+                .unwrap()
+                .arcs()
+                .unwrap(),
+            [1, 39]
+        );
+
+        assert!("2.18446744073709551535".parse::<ObjectIdentifier>().is_ok());
+        assert!(matches!(
+            ObjectIdentifier::from_arcs(&[2, u64::MAX - 79]),
+            Err(DerError::IntValueTooLarge)
+        ));
+        assert!("1.40.3".parse::<ObjectIdentifier>().is_err());
+    }
+
+    #[test]
+    fn oid_uuid_arc() {
+        // 2.25 followed by the UUID f81d4fae-7dec-11d0-a765-00a0c91e6bf6
+        let encoding = [
+            0x06, 0x14, 0x69, 0x83, 0xF0, 0x9D, 0xA7, 0xEB, 0xCF, 0xDE, 0xE0, 0xC7, 0xA1, 0xA7,
+            0xB2, 0xC0, 0x94, 0x8C, 0xC8, 0xF9, 0xD7, 0x76,
+        ];
+        let oid = ObjectIdentifier::deserialize(&mut &encoding[..]).unwrap();
+        assert_eq!(
+            oid.to_string(),
+            "2.25.329800735698586629295641978511506172918"
+        );
+        assert_eq!(oid.to_der_vec(), encoding);
+        assert!(matches!(oid.arcs(), Err(DerError::IntValueTooLarge)));
+
+        // The same value as the first subidentifier, which is 80 more than
+        // the second arc under 2
+        let oid = ObjectIdentifier::from_bytes(&encoding[3..]).unwrap();
+        assert_eq!(oid.to_string(), "2.329800735698586629295641978511506172838");
+
+        // Borrowing across limbs
+        let oid = ObjectIdentifier::from_arcs(&[2, 999_999_920]).unwrap();
+        assert_eq!(oid.to_string(), "2.999999920");
+    }
+
+    #[test]
+    fn oid_requires_two_arcs() {
+        assert!(matches!(
+            ObjectIdentifier::deserialize(&mut &[0x06, 0x00][..]),
+            Err(DerError::InvalidEncoding)
+        ));
+        assert!(ObjectIdentifier::from_bytes(&[]).is_err());
+
+        for s in ["1", "", "1.", ".3"] {
+            assert!(matches!(
+                s.parse::<ObjectIdentifier>(),
+                Err(DerError::InvalidEncoding)
+            ));
+        }
+        assert!(ObjectIdentifier::from_arcs(&[2]).is_err());
+    }
+
+    #[test]
+    fn oid_rejects_non_minimal_arcs() {
+        let encoding = [0x06, 0x03, 0x2A, 0x80, 0x01];
+        assert!(matches!(
+            ObjectIdentifier::deserialize(&mut &encoding[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn oid_rejects_incomplete_last_arc() {
+        // The last byte still has its continuation bit set
+        for encoding in [[0x06, 0x02, 0x2A, 0x80], [0x06, 0x02, 0x2A, 0x86]] {
+            assert!(matches!(
+                ObjectIdentifier::deserialize(&mut &encoding[..]),
+                Err(DerError::InvalidEncoding)
+            ));
+            assert!(ObjectIdentifier::from_bytes(&encoding[2..]).is_err());
+        }
+    }
+
+    #[test]
+    fn fixed_oid() {
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        struct EcPublicKey;
+
+        impl KnownOid for EcPublicKey {
+            const OID: &'static [u8] = crate::oids::EC_PUBLIC_KEY;
+        }
+
+        let oid = FixedOid::<EcPublicKey>::new();
+        let encoding = ObjectIdentifier::from_bytes(crate::oids::EC_PUBLIC_KEY)
+            .unwrap()
+            .to_der_vec();
+        assert_eq!(oid.to_der_vec(), encoding);
+        assert_eq!(oid.encoded_len(), encoding.len());
+        assert_eq!(
+            FixedOid::<EcPublicKey>::deserialize(&mut &encoding[..]).unwrap(),
+            oid
+        );
+
+        let other = ObjectIdentifier::from_bytes(crate::oids::ED25519)
+            .unwrap()
+            .to_der_vec();
+        assert!(matches!(
+            FixedOid::<EcPublicKey>::deserialize(&mut &other[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+}
@@ -0,0 +1,65 @@
+//! OBJECT IDENTIFIER content bytes for common algorithms, for comparing with
+//! `ObjectIdentifier::as_bytes`.
+
+use crate::oid;
+
+/// rsaEncryption, 1.2.840.113549.1.1.1
+pub const RSA_ENCRYPTION: &[u8] = oid!(1, 2, 840, 113_549, 1, 1, 1);
+/// sha256WithRSAEncryption, 1.2.840.113549.1.1.11
+pub const SHA256_WITH_RSA_ENCRYPTION: &[u8] = oid!(1, 2, 840, 113_549, 1, 1, 11);
+/// sha384WithRSAEncryption, 1.2.840.113549.1.1.12
+pub const SHA384_WITH_RSA_ENCRYPTION: &[u8] = oid!(1, 2, 840, 113_549, 1, 1, 12);
+/// sha512WithRSAEncryption, 1.2.840.113549.1.1.13
+pub const SHA512_WITH_RSA_ENCRYPTION: &[u8] = oid!(1, 2, 840, 113_549, 1, 1, 13);
+
+/// id-ecPublicKey, 1.2.840.10045.2.1
+pub const EC_PUBLIC_KEY: &[u8] = oid!(1, 2, 840, 10045, 2, 1);
+/// ecdsa-with-SHA256, 1.2.840.10045.4.3.2
+pub const ECDSA_WITH_SHA256: &[u8] = oid!(1, 2, 840, 10045, 4, 3, 2);
+/// ecdsa-with-SHA384, 1.2.840.10045.4.3.3
+pub const ECDSA_WITH_SHA384: &[u8] = oid!(1, 2, 840, 10045, 4, 3, 3);
+/// ecdsa-with-SHA512, 1.2.840.10045.4.3.4
+pub const ECDSA_WITH_SHA512: &[u8] = oid!(1, 2, 840, 10045, 4, 3, 4);
+/// secp256r1 (P-256), 1.2.840.10045.3.1.7
+pub const SECP256R1: &[u8] = oid!(1, 2, 840, 10045, 3, 1, 7);
+/// secp384r1 (P-384), 1.3.132.0.34
+pub const SECP384R1: &[u8] = oid!(1, 3, 132, 0, 34);
+
+/// id-sha256, 2.16.840.1.101.3.4.2.1
+pub const SHA256: &[u8] = oid!(2, 16, 840, 1, 101, 3, 4, 2, 1);
+/// id-sha384, 2.16.840.1.101.3.4.2.2
+pub const SHA384: &[u8] = oid!(2, 16, 840, 1, 101, 3, 4, 2, 2);
+/// id-sha512, 2.16.840.1.101.3.4.2.3
+pub const SHA512: &[u8] = oid!(2, 16, 840, 1, 101, 3, 4, 2, 3);
+
+/// id-X25519, 1.3.101.110
+pub const X25519: &[u8] = oid!(1, 3, 101, 110);
+/// id-Ed25519, 1.3.101.112
+pub const ED25519: &[u8] = oid!(1, 3, 101, 112);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ObjectIdentifier;
+
+    #[test]
+    fn const_oids() {
+        let sha256 = ObjectIdentifier::from_bytes(SHA256).unwrap();
+        assert_eq!(sha256.arcs().unwrap(), [2, 16, 840, 1, 101, 3, 4, 2, 1]);
+
+        assert_eq!(
+            RSA_ENCRYPTION,
+            [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01]
+        );
+        assert_eq!(ED25519, [0x2B, 0x65, 0x70]);
+
+        for oid in [SHA256_WITH_RSA_ENCRYPTION, EC_PUBLIC_KEY, SECP384R1, X25519] {
+            let parsed = ObjectIdentifier::from_bytes(oid).unwrap();
+            let from_arcs = ObjectIdentifier::from_arcs(&parsed.arcs().unwrap()).unwrap();
+            assert_eq!(from_arcs.as_bytes(), oid);
+        }
+
+        // Multi-byte first subidentifier
+        assert_eq!(oid!(2, 999, 3), [0x88, 0x37, 0x03]);
+    }
+}
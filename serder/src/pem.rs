@@ -0,0 +1,232 @@
+//! PEM armor (RFC 7468): base64 DER between `-----BEGIN label-----` and
+//! `-----END label-----` lines.
+
+use crate::{decode_remaining, DerDeserialize, DerError};
+
+const BEGIN: &str = "-----BEGIN ";
+const END: &str = "-----END ";
+const DASHES: &str = "-----";
+
+/// Decodes the first PEM block in `pem`, returning its label and DER.
+pub fn from_pem(pem: &str) -> Result<(String, Vec<u8>), DerError> {
+    let mut lines = pem.lines();
+
+    next_block(&mut lines)?.ok_or(DerError::UnexpectedEof)
+}
+
+/// Decodes every PEM block in `pem`, such as a CA bundle, in order. Anything
+/// between blocks, like comments and blank lines, is skipped.
+pub fn from_pem_bundle(pem: &str) -> Result<Vec<(String, Vec<u8>)>, DerError> {
+    let mut lines = pem.lines();
+    let mut blocks = Vec::new();
+
+    while let Some(block) = next_block(&mut lines)? {
+        blocks.push(block);
+    }
+
+    Ok(blocks)
+}
+
+/// Decodes a single `T` from either PEM or DER, treating `input` as PEM when
+/// it starts with a `-----BEGIN` line, ignoring leading whitespace. Only the
+/// first PEM block is read. The DER must hold exactly one value.
+pub fn decode_auto<T: DerDeserialize>(input: &[u8]) -> Result<T, DerError> {
+    let start = input
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(input.len());
+
+    let der;
+    let bytes = if input[start..].starts_with(BEGIN.as_bytes()) {
+        let pem = std::str::from_utf8(&input[start..]).map_err(|_| DerError::InvalidEncoding)?;
+        der = from_pem(pem)?.1;
+        &der[..]
+    } else {
+        input
+    };
+
+    let (value, rest) = decode_remaining(bytes)?;
+
+    if !rest.is_empty() {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    Ok(value)
+}
+
+fn armor_label<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    line.trim_end().strip_prefix(prefix)?.strip_suffix(DASHES)
+}
+
+fn next_block<'a>(
+    lines: &mut impl Iterator<Item = &'a str>,
+) -> Result<Option<(String, Vec<u8>)>, DerError> {
+    let label = match lines.find_map(|line| armor_label(line, BEGIN)) {
+        Some(label) => label,
+        None => return Ok(None),
+    };
+
+    let mut base64 = String::new();
+
+    for line in lines {
+        if let Some(end) = armor_label(line, END) {
+            if end != label {
+                return Err(DerError::InvalidEncoding);
+            }
+
+            return Ok(Some((label.to_string(), decode_base64(&base64)?)));
+        }
+
+        base64.extend(line.chars().filter(|c| !c.is_ascii_whitespace()));
+    }
+
+    Err(DerError::UnexpectedEof)
+}
+
+fn base64_value(c: u8) -> Result<u32, DerError> {
+    match c {
+        b'A'..=b'Z' => Ok((c - b'A') as u32),
+        b'a'..=b'z' => Ok((c - b'a' + 26) as u32),
+        b'0'..=b'9' => Ok((c - b'0' + 52) as u32),
+        b'+' => Ok(62),
+        b'/' => Ok(63),
+        _ => Err(DerError::InvalidEncoding),
+    }
+}
+
+// Padded standard base64, with the unused bits of the last group zero
+fn decode_base64(text: &str) -> Result<Vec<u8>, DerError> {
+    let text = text.as_bytes();
+
+    if !text.len().is_multiple_of(4) {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+
+    for (i, group) in text.chunks(4).enumerate() {
+        let last = i == text.len() / 4 - 1;
+        let padding = group.iter().rev().take_while(|&&c| c == b'=').count();
+
+        if padding > 2 || (padding > 0 && !last) {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        let mut value = 0u32;
+        for &c in &group[..4 - padding] {
+            value = value << 6 | base64_value(c)?;
+        }
+        value <<= 6 * padding as u32;
+
+        let decoded = value.to_be_bytes();
+        let len = 3 - padding;
+
+        if decoded[1 + len..].iter().any(|&b| b != 0) {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        bytes.extend_from_slice(&decoded[1..1 + len]);
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnyTlv, DerDeserialize, SEQUENCE};
+
+    // Two self-signed P-256 certificates, CN=Test CA 1 and CN=Test CA 2
+    const BUNDLE: &str = "\
+# Test CA 1
+-----BEGIN CERTIFICATE-----
+MIIBfjCCASOgAwIBAgIUZbSWlr2s4Ljq+Wfzpf3zynyf3pYwCgYIKoZIzj0EAwIw
+FDESMBAGA1UEAwwJVGVzdCBDQSAxMB4XDTI2MTAxNDEwMDYzM1oXDTM2MTAxMTEw
+MDYzM1owFDESMBAGA1UEAwwJVGVzdCBDQSAxMFkwEwYHKoZIzj0CAQYIKoZIzj0D
+AQcDQgAEIzHdxnLzgo4r1xEgAHXY8ydon36KX/FUPMYiDmkisNYlOyI77xT3tPXE
+gb7crLPj2sa4zbsDoHzzN+F+i3FNGKNTMFEwHQYDVR0OBBYEFIuwlRURSKKkCUk/
+oua7mnRtfUJKMB8GA1UdIwQYMBaAFIuwlRURSKKkCUk/oua7mnRtfUJKMA8GA1Ud
+EwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSQAwRgIhANzWraZyn0lsD5X1uri1LDn6
+NSr2f+HlLgZW+tpoQD6NAiEAqgh68jpnLDmUvkCSrdOG1wlN1e/JgWmDSEpztx85
+B5w=
+-----END CERTIFICATE-----
+
+# Test CA 2
+-----BEGIN CERTIFICATE-----
+MIIBfTCCASOgAwIBAgIUb0N4VBAf7xGywOizfO9rX4MfMFEwCgYIKoZIzj0EAwIw
+FDESMBAGA1UEAwwJVGVzdCBDQSAyMB4XDTI2MTAxNDEwMDYzM1oXDTM2MTAxMTEw
+MDYzM1owFDESMBAGA1UEAwwJVGVzdCBDQSAyMFkwEwYHKoZIzj0CAQYIKoZIzj0D
+AQcDQgAE/QLZ7e8ABtnvb7gJQUYWA3QQl6aF7LBQh90lG2w6ZRKswkHS41UQyVog
+t2HXrYe9H1S+ntyZFqRZx7sUluk9X6NTMFEwHQYDVR0OBBYEFL0SCPulUC/UCzxP
+5GGeQgb+d4ziMB8GA1UdIwQYMBaAFL0SCPulUC/UCzxP5GGeQgb+d4ziMA8GA1Ud
+EwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIhALRHkj+lNcoshYaiGTGni+qX
+mV9njtA/zaccSdHs1zSsAiAUsmRQcu0DkJ4nTNyio/eKVNj1kLjaBfyNn5AKVYHx
+ew==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn pem_bundle() {
+        let blocks = from_pem_bundle(BUNDLE).unwrap();
+        assert_eq!(blocks.len(), 2);
+
+        for ((label, der), len) in blocks.iter().zip([0x182, 0x181]) {
+            assert_eq!(label, "CERTIFICATE");
+            assert_eq!(der.len(), len);
+
+            let certificate = AnyTlv::deserialize(&mut &der[..]).unwrap();
+            assert_eq!(certificate.tag(), SEQUENCE);
+            assert_eq!(certificate.as_bytes().len(), der.len());
+        }
+
+        assert_eq!(from_pem(BUNDLE).unwrap(), blocks[0]);
+        assert!(from_pem_bundle("# no blocks\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn decode_pem_or_der() {
+        use crate::AnyTlv;
+
+        let pem = &BUNDLE[BUNDLE.find(BEGIN).unwrap()..BUNDLE.find("\n\n").unwrap()];
+        let (_, der) = from_pem(pem).unwrap();
+
+        let from_pem = decode_auto::<AnyTlv>(pem.as_bytes()).unwrap();
+        let from_der = decode_auto::<AnyTlv>(&der).unwrap();
+        assert_eq!(from_pem, from_der);
+        assert_eq!(from_der.as_bytes(), &der[..]);
+
+        let indented = format!("\n  {}", pem);
+        assert_eq!(
+            decode_auto::<AnyTlv>(indented.as_bytes()).unwrap(),
+            from_der
+        );
+
+        let mut trailing = der.clone();
+        trailing.extend_from_slice(&[0x05, 0x00]);
+        assert!(matches!(
+            decode_auto::<AnyTlv>(&trailing),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn pem_malformed() {
+        let mismatched = "-----BEGIN CERTIFICATE-----\nMAA=\n-----END PRIVATE KEY-----\n";
+        assert!(matches!(
+            from_pem(mismatched),
+            Err(DerError::InvalidEncoding)
+        ));
+
+        let unterminated = "-----BEGIN CERTIFICATE-----\nMAA=\n";
+        assert!(matches!(
+            from_pem_bundle(unterminated),
+            Err(DerError::UnexpectedEof)
+        ));
+
+        assert_eq!(decode_base64("MAA=").unwrap(), [0x30, 0x00]);
+        assert!(decode_base64("MAB=").is_err());
+        assert!(decode_base64("MA=A").is_err());
+        assert!(decode_base64("MA").is_err());
+        assert!(decode_base64("M*A=").is_err());
+    }
+}
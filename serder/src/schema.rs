@@ -0,0 +1,233 @@
+use crate::{
+    explicit_tag, read_contents, read_explicit, set_of::set_of_order, AnyTlv, BigInt, BitString,
+    DerDeserialize, DerError, GeneralizedTime, Ia5String, Null, ObjectIdentifier, OctetString,
+    PrintableString, Utf8String, Value, BIT_STRING, BOOLEAN, GENERALIZED_TIME, IA5_STRING, INTEGER,
+    NULL, OBJECT_IDENTIFIER, OCTET_STRING, PRINTABLE_STRING, SEQUENCE, SET, UTF8_STRING,
+};
+use std::cmp::Ordering;
+
+/// A runtime description of an ASN.1 type, for decoding structures whose
+/// shape isn't known at compile time.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Schema {
+    Boolean,
+    Integer,
+    BitString,
+    OctetString,
+    Null,
+    Oid,
+    Utf8String,
+    PrintableString,
+    Ia5String,
+    GeneralizedTime,
+    /// `SEQUENCE { ... }` with the given components in order
+    Sequence(Vec<Schema>),
+    SequenceOf(Box<Schema>),
+    SetOf(Box<Schema>),
+    /// An OPTIONAL component, decoded as `Value::Absent` when missing
+    Optional(Box<Schema>),
+    /// `[N] EXPLICIT` around another type. An `N` of 31 or above never
+    /// matches and fails to decode.
+    ContextTagged(u8, Box<Schema>),
+    /// Any single TLV, captured as `Value::Other`
+    Any,
+}
+
+impl Schema {
+    /// Whether an element with identifier `tag` can start a value of this
+    /// type.
+    pub fn matches_tag(&self, tag: u8) -> bool {
+        let expected = match self {
+            Schema::Boolean => BOOLEAN,
+            Schema::Integer => INTEGER,
+            Schema::BitString => BIT_STRING,
+            Schema::OctetString => OCTET_STRING,
+            Schema::Null => NULL,
+            Schema::Oid => OBJECT_IDENTIFIER,
+            Schema::Utf8String => UTF8_STRING,
+            Schema::PrintableString => PRINTABLE_STRING,
+            Schema::Ia5String => IA5_STRING,
+            Schema::GeneralizedTime => GENERALIZED_TIME,
+            Schema::Sequence(_) | Schema::SequenceOf(_) => SEQUENCE,
+            Schema::SetOf(_) => SET,
+            Schema::Optional(inner) => return inner.matches_tag(tag),
+            // Not representable in a single identifier octet
+            Schema::ContextTagged(number, _) if *number >= 31 => return false,
+            Schema::ContextTagged(number, _) => explicit_tag(*number),
+            Schema::Any => return true,
+        };
+
+        tag == expected
+    }
+}
+
+/// Decodes a single value described by `schema`, which must span all of
+/// `bytes`.
+pub fn decode_with_schema(bytes: &[u8], schema: &Schema) -> Result<Value, DerError> {
+    let mut bytes = bytes;
+    let value = decode(&mut bytes, schema)?;
+
+    if !bytes.is_empty() {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    Ok(value)
+}
+
+fn decode(bytes: &mut &[u8], schema: &Schema) -> Result<Value, DerError> {
+    Ok(match schema {
+        Schema::Boolean => Value::Bool(bool::deserialize(bytes)?),
+        Schema::Integer => Value::Integer(BigInt::deserialize(bytes)?),
+        Schema::BitString => Value::BitString(BitString::deserialize(bytes)?),
+        Schema::OctetString => Value::OctetString(OctetString::deserialize(bytes)?.into_inner()),
+        Schema::Null => {
+            Null::deserialize(bytes)?;
+            Value::Null
+        }
+        Schema::Oid => Value::Oid(ObjectIdentifier::deserialize(bytes)?),
+        Schema::Utf8String => Value::Utf8(Utf8String::deserialize(bytes)?.into_string()),
+        Schema::PrintableString => Value::Printable(PrintableString::deserialize(bytes)?),
+        Schema::Ia5String => Value::Ia5(Ia5String::deserialize(bytes)?),
+        Schema::GeneralizedTime => Value::GeneralizedTime(GeneralizedTime::deserialize(bytes)?),
+        Schema::Sequence(components) => {
+            let mut contents = read_contents(bytes, SEQUENCE)?;
+            let values = components
+                .iter()
+                .map(|component| decode(&mut contents, component))
+                .collect::<Result<_, _>>()?;
+
+            if !contents.is_empty() {
+                return Err(DerError::InvalidEncoding);
+            }
+
+            Value::Sequence(values)
+        }
+        Schema::SequenceOf(element) => {
+            let mut contents = read_contents(bytes, SEQUENCE)?;
+            let mut values = Vec::new();
+
+            while !contents.is_empty() {
+                values.push(decode(&mut contents, element)?);
+            }
+
+            Value::Sequence(values)
+        }
+        Schema::SetOf(element) => {
+            let mut contents = read_contents(bytes, SET)?;
+            let mut values = Vec::new();
+            let mut previous: Option<&[u8]> = None;
+
+            while !contents.is_empty() {
+                let start = contents;
+                values.push(decode(&mut contents, element)?);
+                let encoding = &start[..start.len() - contents.len()];
+
+                if let Some(previous) = previous {
+                    if set_of_order(previous, encoding) == Ordering::Greater {
+                        return Err(DerError::InvalidEncoding);
+                    }
+                }
+
+                previous = Some(encoding);
+            }
+
+            Value::Set(values)
+        }
+        Schema::Optional(inner) => match bytes.first() {
+            Some(&tag) if inner.matches_tag(tag) => decode(bytes, inner)?,
+            _ => Value::Absent,
+        },
+        Schema::ContextTagged(number, inner) => {
+            let contents = read_explicit(bytes, *number)?;
+            Value::ContextTagged(*number, Box::new(decode_with_schema(contents, inner)?))
+        }
+        Schema::Any => Value::Other(AnyTlv::deserialize(bytes)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_sequence() {
+        let schema = Schema::Sequence(vec![
+            Schema::Integer,
+            Schema::Optional(Box::new(Schema::ContextTagged(
+                0,
+                Box::new(Schema::Boolean),
+            ))),
+            Schema::OctetString,
+        ]);
+
+        let buffer = [0x30, 0x08, 0x02, 0x01, 0x2A, 0x04, 0x03, 0x01, 0x02, 0x03];
+        assert_eq!(
+            decode_with_schema(&buffer, &schema).unwrap(),
+            Value::Sequence(vec![
+                Value::Integer(BigInt::from_unsigned_bytes_be(&[42])),
+                Value::Absent,
+                Value::OctetString(vec![1, 2, 3]),
+            ])
+        );
+
+        let buffer = [
+            0x30, 0x0D, 0x02, 0x01, 0x2A, 0xA0, 0x03, 0x01, 0x01, 0xFF, 0x04, 0x03, 0x01, 0x02,
+            0x03,
+        ];
+        assert_eq!(
+            decode_with_schema(&buffer, &schema).unwrap(),
+            Value::Sequence(vec![
+                Value::Integer(BigInt::from_unsigned_bytes_be(&[42])),
+                Value::ContextTagged(0, Box::new(Value::Bool(true))),
+                Value::OctetString(vec![1, 2, 3]),
+            ])
+        );
+
+        // Components in the wrong order
+        let buffer = [0x30, 0x08, 0x04, 0x03, 0x01, 0x02, 0x03, 0x02, 0x01, 0x2A];
+        assert!(matches!(
+            decode_with_schema(&buffer, &schema),
+            Err(DerError::UnexpectedTag { .. })
+        ));
+    }
+
+    #[test]
+    fn schema_collections() {
+        let schema = Schema::SequenceOf(Box::new(Schema::SetOf(Box::new(Schema::Any))));
+
+        let buffer = [0x30, 0x06, 0x31, 0x04, 0x05, 0x00, 0x05, 0x00];
+        let null = Value::Other(AnyTlv::new(NULL, &[]));
+        assert_eq!(
+            decode_with_schema(&buffer, &schema).unwrap(),
+            Value::Sequence(vec![Value::Set(vec![null.clone(), null])])
+        );
+
+        // Trailing data after the value
+        let buffer = [0x30, 0x00, 0x05, 0x00];
+        assert!(matches!(
+            decode_with_schema(&buffer, &schema),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn schema_high_context_tag_matches() {
+        let schema = Schema::ContextTagged(31, Box::new(Schema::Null));
+        for tag in 0..=u8::MAX {
+            assert!(!schema.matches_tag(tag));
+        }
+    }
+
+    #[test]
+    fn schema_high_context_tag_decode() {
+        let schema = Schema::ContextTagged(31, Box::new(Schema::Null));
+
+        // `[31] EXPLICIT NULL` in the high-tag-number form, and `[8]`, which
+        // 40 would otherwise have been truncated to
+        let buffer = [0xBF, 0x1F, 0x02, 0x05, 0x00];
+        assert!(decode_with_schema(&buffer, &schema).is_err());
+        let schema = Schema::ContextTagged(40, Box::new(Schema::Null));
+        let buffer = [0xA8, 0x02, 0x05, 0x00];
+        assert!(decode_with_schema(&buffer, &schema).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{read_contents, tlv_len, write_header, DerDeserialize, DerError, DerSerialize, SET};
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    io::{self, Write},
+};
+
+/// The X.690 SET OF ordering: encodings compared as octet strings, with the
+/// shorter one padded with trailing zero octets.
+pub(crate) fn set_of_order(a: &[u8], b: &[u8]) -> Ordering {
+    let len = a.len().max(b.len());
+    let padded = |bytes: &[u8], i: usize| bytes.get(i).copied().unwrap_or(0);
+
+    (0..len)
+        .map(|i| padded(a, i).cmp(&padded(b, i)))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// A SET OF, kept in DER order: ascending by the encoding of each element.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SetOf<T>(Vec<T>);
+
+impl<T: DerSerialize> SetOf<T> {
+    /// Sorts `elements` into DER order.
+    pub fn new(elements: Vec<T>) -> Self {
+        let mut encoded: Vec<_> = elements
+            .into_iter()
+            .map(|element| (element.to_der_vec(), element))
+            .collect();
+
+        encoded.sort_by(|(a, _), (b, _)| set_of_order(a, b));
+
+        SetOf(encoded.into_iter().map(|(_, element)| element).collect())
+    }
+
+    /// Wraps elements the caller has already sorted into DER order, skipping
+    /// the encode and sort `new` does. The order is only checked in debug
+    /// builds.
+    pub fn from_sorted_unchecked(elements: Vec<T>) -> Self {
+        debug_assert!(
+            elements.windows(2).all(|pair| {
+                set_of_order(&pair[0].to_der_vec(), &pair[1].to_der_vec()) != Ordering::Greater
+            }),
+            "SET OF elements are not in DER order"
+        );
+
+        SetOf(elements)
+    }
+}
+
+impl<T> SetOf<T> {
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SetOf<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<T: DerSerialize> DerSerialize for SetOf<T> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let content_len = self.0.iter().map(DerSerialize::encoded_len).sum();
+        let mut written = write_header(SET, content_len, writer)?;
+
+        for element in &self.0 {
+            written += element.serialize(writer)?;
+        }
+
+        Ok(written)
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.0.iter().map(DerSerialize::encoded_len).sum())
+    }
+}
+
+impl<T: DerDeserialize> DerDeserialize for SetOf<T> {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        let mut contents = read_contents(bytes, SET)?;
+        let mut elements = Vec::new();
+        let mut previous: Option<&[u8]> = None;
+
+        while !contents.is_empty() {
+            let start = contents;
+            elements.push(T::deserialize(&mut contents)?);
+            let encoding = &start[..start.len() - contents.len()];
+
+            // DER requires the elements to already be in order
+            if let Some(previous) = previous {
+                if set_of_order(previous, encoding) == Ordering::Greater {
+                    return Err(DerError::InvalidEncoding);
+                }
+            }
+
+            previous = Some(encoding);
+        }
+
+        Ok(SetOf(elements))
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == SET
+    }
+}
+
+// SET OF, sorted by encoding rather than by `Ord` when serialized
+impl<T: DerSerialize> DerSerialize for BTreeSet<T> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let mut encoded: Vec<_> = self.iter().map(DerSerialize::to_der_vec).collect();
+        encoded.sort_by(|a, b| set_of_order(a, b));
+
+        let content_len = encoded.iter().map(Vec::len).sum();
+        let written = write_header(SET, content_len, writer)?;
+
+        for element in &encoded {
+            writer.write_all(element)?;
+        }
+
+        Ok(written + content_len)
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.iter().map(DerSerialize::encoded_len).sum())
+    }
+}
+
+impl<T: DerDeserialize + Ord> DerDeserialize for BTreeSet<T> {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        let elements = SetOf::<T>::deserialize(bytes)?.into_inner();
+        let len = elements.len();
+        let set: BTreeSet<T> = elements.into_iter().collect();
+
+        // Duplicate elements are valid DER but can't be kept in a set
+        if set.len() != len {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(set)
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == SET
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_of_enc_dec() {
+        let set = SetOf::new(vec![300u32, 5, 70_000, 1]);
+        assert_eq!(set.as_slice(), [1, 5, 300, 70_000]);
+
+        let buffer = set.to_der_vec();
+        assert_eq!(
+            buffer,
+            [
+                0x31, 0x0F, 0x02, 0x01, 0x01, 0x02, 0x01, 0x05, 0x02, 0x02, 0x01, 0x2C, 0x02, 0x03,
+                0x01, 0x11, 0x70
+            ]
+        );
+        assert_eq!(SetOf::<u32>::deserialize(&mut &buffer[..]).unwrap(), set);
+
+        // Out of order elements
+        let buffer = [0x31, 0x06, 0x02, 0x01, 0x05, 0x02, 0x01, 0x01];
+        assert!(matches!(
+            SetOf::<u32>::deserialize(&mut &buffer[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn empty_collections() {
+        let empty = Vec::<u32>::new();
+        assert_eq!(empty.to_der_vec(), [0x30, 0x00]);
+        assert_eq!(
+            Vec::<u32>::deserialize(&mut &[0x30, 0x00][..]).unwrap(),
+            empty
+        );
+
+        let empty = BTreeSet::<u32>::new();
+        assert_eq!(empty.to_der_vec(), [0x31, 0x00]);
+        assert_eq!(
+            BTreeSet::<u32>::deserialize(&mut &[0x31, 0x00][..]).unwrap(),
+            empty
+        );
+
+        let empty = SetOf::<u32>::new(Vec::new());
+        assert_eq!(empty.to_der_vec(), [0x31, 0x00]);
+        assert!(SetOf::<u32>::deserialize(&mut &[0x31, 0x00][..])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn btree_set_of() {
+        // 256 sorts before 5 as its encoding is longer
+        let set = BTreeSet::from([5u32, 256, 1]);
+        let buffer = set.to_der_vec();
+        assert_eq!(
+            buffer,
+            [0x31, 0x0A, 0x02, 0x01, 0x01, 0x02, 0x01, 0x05, 0x02, 0x02, 0x01, 0x00]
+        );
+        assert_eq!(BTreeSet::<u32>::deserialize(&mut &buffer[..]).unwrap(), set);
+
+        let duplicates = [0x31, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01];
+        assert!(BTreeSet::<u32>::deserialize(&mut &duplicates[..]).is_err());
+    }
+
+    #[test]
+    fn set_of_from_sorted() {
+        let sorted = vec![1u32, 5, 300, 70_000];
+        assert_eq!(
+            SetOf::from_sorted_unchecked(sorted.clone()).to_der_vec(),
+            SetOf::new(sorted).to_der_vec()
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "SET OF elements are not in DER order")]
+    fn set_of_from_unsorted() {
+        SetOf::from_sorted_unchecked(vec![5u32, 1]);
+    }
+
+    // Writes its bytes verbatim, standing in for an element's encoding
+    #[derive(Debug, PartialEq)]
+    struct Raw(&'static [u8]);
+
+    impl DerSerialize for Raw {
+        fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+            writer.write_all(self.0)?;
+
+            Ok(self.0.len())
+        }
+    }
+
+    #[test]
+    fn set_of_order_pads_with_zeros() {
+        // Equal once the shorter is padded, unlike with slice `Ord`
+        assert_eq!(set_of_order(&[0x01], &[0x01, 0x00]), Ordering::Equal);
+        assert_eq!([0x01][..].cmp(&[0x01, 0x00][..]), Ordering::Less);
+
+        assert_eq!(set_of_order(&[0x01], &[0x01, 0x01]), Ordering::Less);
+        assert_eq!(
+            set_of_order(&[0x01, 0x00, 0x01], &[0x01]),
+            Ordering::Greater
+        );
+        assert_eq!(set_of_order(&[0x02], &[0x01, 0xFF]), Ordering::Greater);
+
+        // Ties keep their original order
+        let set = SetOf::new(vec![Raw(&[0x01, 0x00]), Raw(&[0x01]), Raw(&[0x00, 0xFF])]);
+        assert_eq!(
+            set.as_slice(),
+            [Raw(&[0x00, 0xFF]), Raw(&[0x01, 0x00]), Raw(&[0x01])]
+        );
+        let set = SetOf::new(vec![Raw(&[0x01]), Raw(&[0x01, 0x00])]);
+        assert_eq!(set.as_slice(), [Raw(&[0x01]), Raw(&[0x01, 0x00])]);
+    }
+}
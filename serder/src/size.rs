@@ -0,0 +1,151 @@
+//! ASN.1 SIZE constraints, checked when a value is built or decoded.
+
+use crate::{
+    BitString, DerDeserialize, DerError, DerSerialize, Ia5String, OctetString, PrintableString,
+    SetOf, Utf8String,
+};
+use std::io::{self, Write};
+
+/// A type a SIZE constraint applies to, and what it measures.
+pub trait SizeConstrained {
+    fn size(&self) -> usize;
+}
+
+// Octets
+impl SizeConstrained for OctetString {
+    fn size(&self) -> usize {
+        self.as_bytes().len()
+    }
+}
+
+// Bits
+impl SizeConstrained for BitString {
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+// Elements
+impl<T> SizeConstrained for Vec<T> {
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+// Elements
+impl<T> SizeConstrained for SetOf<T> {
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+macro_rules! string_size {
+    ($($t:ty),+) => {$(
+        // Characters
+        impl SizeConstrained for $t {
+            fn size(&self) -> usize {
+                self.as_str().chars().count()
+            }
+        }
+    )+}
+}
+
+string_size!(Utf8String, PrintableString, Ia5String);
+
+/// A value with the constraint `SIZE (MIN..MAX)`, such as
+/// `SizeBounded<OctetString, 1, 4>` for `OCTET STRING (SIZE (1..4))`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SizeBounded<T, const MIN: usize, const MAX: usize>(T);
+
+impl<T: SizeConstrained, const MIN: usize, const MAX: usize> SizeBounded<T, MIN, MAX> {
+    /// Fails with `DerError::SizeOutOfRange` if `value` doesn't meet the
+    /// constraint.
+    pub fn new(value: T) -> Result<Self, DerError> {
+        let size = value.size();
+
+        if !(MIN..=MAX).contains(&size) {
+            return Err(DerError::SizeOutOfRange {
+                size,
+                min: MIN,
+                max: MAX,
+            });
+        }
+
+        Ok(SizeBounded(value))
+    }
+}
+
+impl<T, const MIN: usize, const MAX: usize> SizeBounded<T, MIN, MAX> {
+    pub fn get(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DerSerialize, const MIN: usize, const MAX: usize> DerSerialize
+    for SizeBounded<T, MIN, MAX>
+{
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        self.0.serialize(writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl<T, const MIN: usize, const MAX: usize> DerDeserialize for SizeBounded<T, MIN, MAX>
+where
+    T: DerDeserialize + SizeConstrained,
+{
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        SizeBounded::new(T::deserialize(bytes)?)
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        T::matches_tag(tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn octet_string_size() {
+        type Short = SizeBounded<OctetString, 1, 4>;
+
+        let buffer = [0x04, 0x03, 0x01, 0x02, 0x03];
+        let value = Short::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(value.get().as_bytes(), [0x01, 0x02, 0x03]);
+        assert_eq!(value.to_der_vec(), buffer);
+
+        let buffer = [0x04, 0x05, 0x01, 0x02, 0x03, 0x04, 0x05];
+        assert!(matches!(
+            Short::deserialize(&mut &buffer[..]),
+            Err(DerError::SizeOutOfRange {
+                size: 5,
+                min: 1,
+                max: 4
+            })
+        ));
+        assert!(Short::new(OctetString::default()).is_err());
+    }
+
+    #[test]
+    fn element_and_character_sizes() {
+        let buffer = vec![1u8, 2, 3].to_der_vec();
+        assert!(SizeBounded::<Vec<u8>, 1, 3>::deserialize(&mut &buffer[..]).is_ok());
+        assert!(SizeBounded::<Vec<u8>, 0, 2>::deserialize(&mut &buffer[..]).is_err());
+
+        // Characters rather than bytes
+        let name = Utf8String::new("héllo").unwrap();
+        assert!(SizeBounded::<_, 1, 5>::new(name).is_ok());
+
+        let bits = BitString::new(vec![0xF0], 4).unwrap();
+        assert!(SizeBounded::<_, 5, 8>::new(bits).is_err());
+    }
+}
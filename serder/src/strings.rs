@@ -0,0 +1,180 @@
+use crate::{
+    read_contents, tlv_len, write_header, DerDeserialize, DerDeserializeBorrowed, DerError,
+    DerSerialize, DerSerializeContent, IA5_STRING, PRINTABLE_STRING, UTF8_STRING,
+};
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+fn is_printable(c: char) -> bool {
+    c.is_ascii_alphanumeric() || " '()+,-./:=?".contains(c)
+}
+
+macro_rules! string_type {
+    ($(#[$meta:meta])* $name:ident, $borrowed:ident, $tag:ident, $valid:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(String);
+
+        #[doc = concat!("A borrowed `", stringify!($name), "`, referencing the decoded input.")]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $borrowed<'a>(&'a str);
+
+        impl<'a> $borrowed<'a> {
+            pub fn as_str(&self) -> &'a str {
+                self.0
+            }
+        }
+
+        impl fmt::Display for $borrowed<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+
+        impl<'a> DerDeserializeBorrowed<'a> for $borrowed<'a> {
+            fn deserialize_borrowed(bytes: &mut &'a [u8]) -> Result<Self, DerError> {
+                let contents = read_contents(bytes, $tag)?;
+                let s = std::str::from_utf8(contents).map_err(|_| DerError::InvalidEncoding)?;
+
+                if s.chars().all($valid) {
+                    Ok($borrowed(s))
+                } else {
+                    Err(DerError::InvalidEncoding)
+                }
+            }
+        }
+
+        impl $name {
+            pub fn new(s: impl Into<String>) -> Result<Self, DerError> {
+                let s = s.into();
+
+                if s.chars().all($valid) {
+                    Ok($name(s))
+                } else {
+                    Err(DerError::InvalidEncoding)
+                }
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl DerSerializeContent for $name {
+            fn serialize_content<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+                writer.write_all(self.0.as_bytes())?;
+
+                Ok(self.0.len())
+            }
+        }
+
+        impl DerSerialize for $name {
+            fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+                let written = write_header($tag, self.0.len(), writer)?;
+                writer.write_all(self.0.as_bytes())?;
+
+                Ok(written + self.0.len())
+            }
+
+            fn encoded_len(&self) -> usize {
+                tlv_len(self.0.len())
+            }
+        }
+
+        impl DerDeserialize for $name {
+            fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+                $borrowed::deserialize_borrowed(bytes).map(|s| $name(s.0.to_string()))
+            }
+
+            fn matches_tag(tag: u8) -> bool {
+                tag == $tag
+            }
+        }
+    };
+}
+
+string_type!(
+    /// A UTF8String.
+    Utf8String,
+    Utf8Str,
+    UTF8_STRING,
+    |_| true
+);
+
+string_type!(
+    /// A PrintableString: letters, digits, space, and `'()+,-./:=?`.
+    PrintableString,
+    PrintableStr,
+    PRINTABLE_STRING,
+    is_printable
+);
+
+string_type!(
+    /// An IA5String, limited to ASCII.
+    Ia5String,
+    Ia5Str,
+    IA5_STRING,
+    |c: char| c.is_ascii()
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_enc_dec() {
+        let s = Utf8String::new("héllo").unwrap();
+        let buffer = s.to_der_vec();
+        assert_eq!(buffer, [0x0C, 0x06, b'h', 0xC3, 0xA9, b'l', b'l', b'o']);
+        assert_eq!(Utf8String::deserialize(&mut &buffer[..]).unwrap(), s);
+        assert!(Utf8String::deserialize(&mut &[0x0C, 0x01, 0xFF][..]).is_err());
+
+        let s = PrintableString::new("Example Corp.").unwrap();
+        let buffer = s.to_der_vec();
+        assert_eq!(buffer[0], PRINTABLE_STRING);
+        assert_eq!(PrintableString::deserialize(&mut &buffer[..]).unwrap(), s);
+        assert!(PrintableString::new("a@b").is_err());
+        assert!(PrintableString::deserialize(&mut &[0x13, 0x01, b'*'][..]).is_err());
+
+        let s = Ia5String::new("user@example.com").unwrap();
+        let buffer = s.to_der_vec();
+        assert_eq!(buffer[0], IA5_STRING);
+        assert_eq!(Ia5String::deserialize(&mut &buffer[..]).unwrap(), s);
+        assert!(Ia5String::new("é").is_err());
+    }
+
+    #[test]
+    fn borrowed_strings() {
+        let buffer = [0x0C, 0x06, b'h', 0xC3, 0xA9, b'l', b'l', b'o', 0x05, 0x00];
+        let mut bytes = &buffer[..];
+        let s = Utf8Str::deserialize_borrowed(&mut bytes).unwrap();
+        assert_eq!(s.as_str(), "héllo");
+        assert_eq!(s.as_str().as_ptr(), buffer[2..].as_ptr());
+        assert_eq!(bytes, [0x05, 0x00]);
+
+        let buffer = PrintableString::new("Example Corp.").unwrap().to_der_vec();
+        let s = PrintableStr::deserialize_borrowed(&mut &buffer[..]).unwrap();
+        assert_eq!(s.to_string(), "Example Corp.");
+        assert_eq!(s.as_str().as_ptr(), buffer[2..].as_ptr());
+
+        assert!(Utf8Str::deserialize_borrowed(&mut &[0x0C, 0x01, 0xFF][..]).is_err());
+        assert!(PrintableStr::deserialize_borrowed(&mut &[0x13, 0x01, b'*'][..]).is_err());
+        assert!(Ia5Str::deserialize_borrowed(&mut &[0x16, 0x02, 0xC3, 0xA9][..]).is_err());
+        assert!(matches!(
+            Ia5Str::deserialize_borrowed(&mut &[0x0C, 0x01, b'a'][..]),
+            Err(DerError::UnexpectedTag { .. })
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{read_contents, tlv_len, write_header, DerDeserialize, DerError, DerSerialize, Tag};
+use std::io::{self, Write};
+
+/// The identifier of the constructed context-specific tag `[number]`.
+///
+/// Panics if `number` is 31 or above, which would need the high-tag-number
+/// form rather than a single identifier octet.
+pub const fn explicit_tag(number: u8) -> u8 {
+    assert!(
+        number < 31,
+        "high tag numbers aren't supported for explicit tags"
+    );
+
+    Tag::new(number)
+        .context_specific()
+        .constructed()
+        .into_tag_value()
+}
+
+/// Reads an explicit `[number]` wrapper and returns its content, the complete
+/// encoding of the wrapped value. The content may be empty, in which case
+/// decoding the inner value will report what's missing. A `number` of 31 or
+/// above is rejected as `DerError::InvalidEncoding`, since those tags need the
+/// high-tag-number form.
+pub fn read_explicit<'a>(bytes: &mut &'a [u8], number: u8) -> Result<&'a [u8], DerError> {
+    if number >= 31 {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    read_contents(bytes, explicit_tag(number))
+}
+
+/// `[N] EXPLICIT T`: the encoding of `T` wrapped in a constructed
+/// context-specific tag. `N` must be below 31.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Explicit<const N: u8, T>(pub T);
+
+impl<const N: u8, T> Explicit<N, T> {
+    const TAG: u8 = explicit_tag(N);
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<const N: u8, T: DerSerialize> DerSerialize for Explicit<N, T> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let written = write_header(Self::TAG, self.0.encoded_len(), writer)?;
+
+        Ok(written + self.0.serialize(writer)?)
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.0.encoded_len())
+    }
+}
+
+impl<const N: u8, T: DerDeserialize> DerDeserialize for Explicit<N, T> {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        let mut contents = read_contents(bytes, Self::TAG)?;
+        let value = T::deserialize(&mut contents)?;
+
+        if !contents.is_empty() {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(Explicit(value))
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == Self::TAG
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DerDeserialize, DerSerialize, Null};
+
+    #[test]
+    fn explicit_enc_dec() {
+        let value = Explicit::<1, u32>(300);
+        let buffer = value.to_der_vec();
+        assert_eq!(buffer, [0xA1, 0x04, 0x02, 0x02, 0x01, 0x2C]);
+        assert_eq!(
+            Explicit::<1, u32>::deserialize(&mut &buffer[..]).unwrap(),
+            value
+        );
+        assert_eq!(value.encoded_len(), buffer.len());
+
+        // Wrong tag number
+        assert!(matches!(
+            Explicit::<2, u32>::deserialize(&mut &buffer[..]),
+            Err(DerError::UnexpectedTag { .. })
+        ));
+
+        // More than one value inside the wrapper
+        let buffer = [0xA1, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02];
+        assert!(matches!(
+            Explicit::<1, u32>::deserialize(&mut &buffer[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn explicit_empty_inner_value() {
+        let buffer = [0xA0, 0x02, 0x05, 0x00];
+        assert_eq!(Explicit::<0, Null>(Null).to_der_vec(), buffer);
+        assert_eq!(
+            Explicit::<0, Null>::deserialize(&mut &buffer[..]).unwrap(),
+            Explicit(Null)
+        );
+
+        let buffer = [0xA0, 0x02, 0x30, 0x00];
+        assert_eq!(
+            Explicit::<0, Vec<u8>>::deserialize(&mut &buffer[..]).unwrap(),
+            Explicit(vec![])
+        );
+
+        // A zero length wrapper is handed to the inner decoder as empty input
+        let buffer = [0xA0, 0x00];
+        assert!(read_explicit(&mut &buffer[..], 0).unwrap().is_empty());
+        assert!(matches!(
+            Explicit::<0, Null>::deserialize(&mut &buffer[..]),
+            Err(DerError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            Explicit::<0, Option<Null>>::deserialize(&mut &buffer[..]),
+            Ok(Explicit(None))
+        ));
+    }
+
+    #[test]
+    fn explicit_tag_number_out_of_range() {
+        // [8] inside, which 40 would otherwise have been truncated to
+        let buffer = [0xA8, 0x03, 0x02, 0x01, 0x05];
+        assert!(read_explicit(&mut &buffer[..], 40).is_err());
+        assert!(read_explicit(&mut &buffer[..], 31).is_err());
+    }
+
+    // `SEQUENCE { version [0] EXPLICIT INTEGER OPTIONAL, serial INTEGER }`
+    #[derive(Debug, PartialEq, DerSerialize, DerDeserialize)]
+    struct Versioned {
+        version: Option<Explicit<0, u8>>,
+        serial: u32,
+    }
+
+    #[test]
+    fn optional_explicit() {
+        let present = Versioned {
+            version: Some(Explicit(2)),
+            serial: 7,
+        };
+        let buffer = present.to_der_vec();
+        assert_eq!(
+            buffer,
+            [0x30, 0x08, 0xA0, 0x03, 0x02, 0x01, 0x02, 0x02, 0x01, 0x07]
+        );
+        assert_eq!(Versioned::deserialize(&mut &buffer[..]).unwrap(), present);
+
+        // The whole wrapper is omitted
+        let absent = Versioned {
+            version: None,
+            serial: 7,
+        };
+        let buffer = absent.to_der_vec();
+        assert_eq!(buffer, [0x30, 0x03, 0x02, 0x01, 0x07]);
+        assert_eq!(Versioned::deserialize(&mut &buffer[..]).unwrap(), absent);
+
+        // A different context tag isn't the optional component
+        let buffer = [0x30, 0x08, 0xA1, 0x03, 0x02, 0x01, 0x02, 0x02, 0x01, 0x07];
+        assert!(Versioned::deserialize(&mut &buffer[..]).is_err());
+    }
+}
@@ -0,0 +1,653 @@
+use crate::{
+    read_contents, tlv_len, write_header, DerDeserialize, DerError, DerSerialize, GENERALIZED_TIME,
+    UTC_TIME,
+};
+use std::{
+    convert::{TryFrom, TryInto},
+    io::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn validate(
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+) -> Result<(), DerError> {
+    let valid = (1..=12).contains(&month)
+        && day >= 1
+        && day <= days_in_month(year, month)
+        && hour < 24
+        && minute < 60
+        && second < 60;
+
+    if valid {
+        Ok(())
+    } else {
+        Err(DerError::InvalidEncoding)
+    }
+}
+
+// Parses exactly `digits.len()` ASCII digits
+fn parse_digits(digits: &[u8]) -> Result<u32, DerError> {
+    digits.iter().try_fold(0u32, |n, digit| match digit {
+        b'0'..=b'9' => Ok(n * 10 + u32::from(digit - b'0')),
+        _ => Err(DerError::InvalidEncoding),
+    })
+}
+
+// Converts days since 1970-01-01 to a proleptic Gregorian year, month and
+// day, using Howard Hinnant's `civil_from_days`
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March, so the leap day is last
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month as u8, day as u8)
+}
+
+// Splits Unix seconds into calendar fields, or `None` if the year isn't
+// between 0 and 9999
+fn from_unix_seconds(seconds: i64) -> Option<(u16, u8, u8, u8, u8, u8)> {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+
+    Some((
+        year as u16,
+        month,
+        day,
+        (time / 3600) as u8,
+        (time / 60 % 60) as u8,
+        (time % 60) as u8,
+    ))
+}
+
+// Seconds and nanoseconds since the Unix epoch, flooring times before it
+fn unix_time(time: SystemTime) -> Result<(i64, u32), DerError> {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => Ok((since.as_secs().try_into()?, since.subsec_nanos())),
+        Err(e) => {
+            let before = e.duration();
+            let seconds = -i64::try_from(before.as_secs())?;
+
+            match before.subsec_nanos() {
+                0 => Ok((seconds, 0)),
+                nanos => Ok((seconds - 1, 1_000_000_000 - nanos)),
+            }
+        }
+    }
+}
+
+/// A GeneralizedTime in the DER form `YYYYMMDDHHMMSS[.f]Z`: always UTC,
+/// seconds always present, and any fractional seconds written with a `.` and
+/// no trailing zeros.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GeneralizedTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+}
+
+impl GeneralizedTime {
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self, DerError> {
+        if year > 9999 {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        validate(year, month, day, hour, minute, second)?;
+
+        Ok(GeneralizedTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            nanosecond: 0,
+        })
+    }
+
+    pub fn with_nanosecond(self, nanosecond: u32) -> Result<Self, DerError> {
+        if nanosecond >= 1_000_000_000 {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        Ok(GeneralizedTime { nanosecond, ..self })
+    }
+
+    /// The time `seconds` after the Unix epoch, which must fall in years 0
+    /// through 9999.
+    pub fn from_unix_seconds(seconds: i64) -> Result<Self, DerError> {
+        let (year, month, day, hour, minute, second) =
+            from_unix_seconds(seconds).ok_or(DerError::InvalidEncoding)?;
+
+        GeneralizedTime::new(year, month, day, hour, minute, second)
+    }
+
+    /// Converts a `SystemTime`, keeping its fractional seconds.
+    pub fn from_system_time(time: SystemTime) -> Result<Self, DerError> {
+        let (seconds, nanosecond) = unix_time(time)?;
+
+        GeneralizedTime::from_unix_seconds(seconds)?.with_nanosecond(nanosecond)
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    pub fn nanosecond(&self) -> u32 {
+        self.nanosecond
+    }
+
+    fn der_string(&self) -> String {
+        let mut s = format!(
+            "{:04}{:02}{:02}{:02}{:02}{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        );
+
+        if self.nanosecond != 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            s.push('.');
+            s.push_str(fraction.trim_end_matches('0'));
+        }
+
+        s.push('Z');
+        s
+    }
+
+    fn parse(contents: &[u8]) -> Result<Self, DerError> {
+        if contents.len() < 15 || contents.last() != Some(&b'Z') {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        let (fields, fraction) = contents[..contents.len() - 1].split_at(14);
+        let field = |start: usize, len: usize| parse_digits(&fields[start..start + len]);
+
+        let time = GeneralizedTime::new(
+            field(0, 4)? as u16,
+            field(4, 2)? as u8,
+            field(6, 2)? as u8,
+            field(8, 2)? as u8,
+            field(10, 2)? as u8,
+            field(12, 2)? as u8,
+        )?;
+
+        if fraction.is_empty() {
+            return Ok(time);
+        }
+
+        // Only a `.` may separate the fraction, which must be non-empty and
+        // have no trailing zeros
+        let digits = match fraction.split_first() {
+            Some((b'.', digits)) if !digits.is_empty() && digits.len() <= 9 => digits,
+            _ => return Err(DerError::InvalidEncoding),
+        };
+
+        if digits.last() == Some(&b'0') {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        let nanosecond = parse_digits(digits)? * 10u32.pow(9 - digits.len() as u32);
+
+        time.with_nanosecond(nanosecond)
+    }
+}
+
+impl DerSerialize for GeneralizedTime {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let s = self.der_string();
+        let written = write_header(GENERALIZED_TIME, s.len(), writer)?;
+        writer.write_all(s.as_bytes())?;
+
+        Ok(written + s.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(self.der_string().len())
+    }
+}
+
+impl DerDeserialize for GeneralizedTime {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        GeneralizedTime::parse(read_contents(bytes, GENERALIZED_TIME)?)
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == GENERALIZED_TIME
+    }
+}
+
+/// A UTCTime in the DER form `YYMMDDHHMMSSZ`. Two digit years are 1950
+/// through 2049, as in RFC 5280.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UtcTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl UtcTime {
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self, DerError> {
+        if !(1950..=2049).contains(&year) {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        validate(year, month, day, hour, minute, second)?;
+
+        Ok(UtcTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// The time `seconds` after the Unix epoch, which must fall in years 1950
+    /// through 2049.
+    pub fn from_unix_seconds(seconds: i64) -> Result<Self, DerError> {
+        let (year, month, day, hour, minute, second) =
+            from_unix_seconds(seconds).ok_or(DerError::InvalidEncoding)?;
+
+        UtcTime::new(year, month, day, hour, minute, second)
+    }
+
+    /// Converts a `SystemTime`, dropping its fractional seconds.
+    pub fn from_system_time(time: SystemTime) -> Result<Self, DerError> {
+        UtcTime::from_unix_seconds(unix_time(time)?.0)
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    fn der_string(&self) -> String {
+        format!(
+            "{:02}{:02}{:02}{:02}{:02}{:02}Z",
+            self.year % 100,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second
+        )
+    }
+
+    fn parse(contents: &[u8]) -> Result<Self, DerError> {
+        // Seconds are required and the time must be in UTC
+        if contents.len() != 13 || contents[12] != b'Z' {
+            return Err(DerError::InvalidEncoding);
+        }
+
+        let field = |start: usize| parse_digits(&contents[start..start + 2]);
+
+        let year = match field(0)? as u16 {
+            year @ 0..=49 => 2000 + year,
+            year => 1900 + year,
+        };
+
+        UtcTime::new(
+            year,
+            field(2)? as u8,
+            field(4)? as u8,
+            field(6)? as u8,
+            field(8)? as u8,
+            field(10)? as u8,
+        )
+    }
+}
+
+impl DerSerialize for UtcTime {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let s = self.der_string();
+        let written = write_header(UTC_TIME, s.len(), writer)?;
+        writer.write_all(s.as_bytes())?;
+
+        Ok(written + s.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        tlv_len(13)
+    }
+}
+
+impl DerDeserialize for UtcTime {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        UtcTime::parse(read_contents(bytes, UTC_TIME)?)
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == UTC_TIME
+    }
+}
+
+/// X.509 `Time ::= CHOICE { utcTime UTCTime, generalTime GeneralizedTime }`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Time {
+    Utc(UtcTime),
+    Generalized(GeneralizedTime),
+}
+
+impl Time {
+    /// Picks UTCTime for years 1950 through 2049 and GeneralizedTime
+    /// otherwise, as RFC 5280 requires.
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self, DerError> {
+        if (1950..=2049).contains(&year) {
+            UtcTime::new(year, month, day, hour, minute, second).map(Time::Utc)
+        } else {
+            GeneralizedTime::new(year, month, day, hour, minute, second).map(Time::Generalized)
+        }
+    }
+
+    /// The time `seconds` after the Unix epoch, picking the type as `new`
+    /// does.
+    pub fn from_unix_seconds(seconds: i64) -> Result<Self, DerError> {
+        let (year, month, day, hour, minute, second) =
+            from_unix_seconds(seconds).ok_or(DerError::InvalidEncoding)?;
+
+        Time::new(year, month, day, hour, minute, second)
+    }
+
+    /// Converts a `SystemTime`, dropping its fractional seconds, which
+    /// certificate validity times can't carry.
+    pub fn from_system_time(time: SystemTime) -> Result<Self, DerError> {
+        Time::from_unix_seconds(unix_time(time)?.0)
+    }
+}
+
+impl DerSerialize for Time {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        match self {
+            Time::Utc(time) => time.serialize(writer),
+            Time::Generalized(time) => time.serialize(writer),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            Time::Utc(time) => time.encoded_len(),
+            Time::Generalized(time) => time.encoded_len(),
+        }
+    }
+}
+
+impl DerDeserialize for Time {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        match bytes.first() {
+            Some(&UTC_TIME) => UtcTime::deserialize(bytes).map(Time::Utc),
+            _ => GeneralizedTime::deserialize(bytes).map(Time::Generalized),
+        }
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == UTC_TIME || tag == GENERALIZED_TIME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(s: &str) -> Vec<u8> {
+        let mut buffer = vec![GENERALIZED_TIME, s.len() as u8];
+        buffer.extend_from_slice(s.as_bytes());
+        buffer
+    }
+
+    fn decode(s: &str) -> Result<GeneralizedTime, DerError> {
+        GeneralizedTime::deserialize(&mut &encode(s)[..])
+    }
+
+    #[test]
+    fn generalized_time_enc_dec() {
+        let time = decode("20210101000000Z").unwrap();
+        assert_eq!(time, GeneralizedTime::new(2021, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(time.to_der_vec(), encode("20210101000000Z"));
+
+        let time = decode("19991231235959.123Z").unwrap();
+        assert_eq!(time.nanosecond(), 123_000_000);
+        assert_eq!(time.to_der_vec(), encode("19991231235959.123Z"));
+
+        let time = GeneralizedTime::new(2000, 2, 29, 12, 30, 45)
+            .unwrap()
+            .with_nanosecond(500_000_000)
+            .unwrap();
+        assert_eq!(time.to_der_vec(), encode("20000229123045.5Z"));
+        assert_eq!(time.encoded_len(), 19);
+
+        assert!(GeneralizedTime::new(1900, 2, 29, 0, 0, 0).is_err());
+        assert!(GeneralizedTime::new(2021, 13, 1, 0, 0, 0).is_err());
+    }
+
+    #[test]
+    fn generalized_time_fraction_canonicalization() {
+        assert_eq!(
+            decode("20210101000000.5Z").unwrap().nanosecond(),
+            500_000_000
+        );
+
+        // Trailing zeros
+        assert!(decode("20210101000000.500Z").is_err());
+        assert!(decode("20210101000000.0Z").is_err());
+        // Comma as the decimal mark
+        assert!(decode("20210101000000,5Z").is_err());
+        // Empty fraction
+        assert!(decode("20210101000000.Z").is_err());
+    }
+
+    #[test]
+    fn generalized_time_rejects_non_der_forms() {
+        // No seconds
+        assert!(decode("202101010000Z").is_err());
+        // Local time and offsets
+        assert!(decode("20210101000000").is_err());
+        assert!(decode("20210101000000+0100").is_err());
+        assert!(decode("2021010100000aZ").is_err());
+        assert!(decode("20210101240000Z").is_err());
+    }
+
+    #[test]
+    fn utc_time_enc_dec() {
+        let buffer = b"\x17\x0D491231235959Z";
+        let time = UtcTime::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(time, UtcTime::new(2049, 12, 31, 23, 59, 59).unwrap());
+        assert_eq!(time.to_der_vec(), buffer);
+
+        let buffer = b"\x17\x0D500101000000Z";
+        assert_eq!(UtcTime::deserialize(&mut &buffer[..]).unwrap().year(), 1950);
+
+        // No seconds, or not UTC
+        assert!(UtcTime::deserialize(&mut &b"\x17\x0B4912312359Z"[..]).is_err());
+        assert!(UtcTime::deserialize(&mut &b"\x17\x0D491231235959+"[..]).is_err());
+        assert!(UtcTime::new(2050, 1, 1, 0, 0, 0).is_err());
+    }
+
+    #[test]
+    fn time_choice() {
+        let buffer = b"\x17\x0D210101000000Z";
+        let time = Time::deserialize(&mut &buffer[..]).unwrap();
+        assert!(matches!(time, Time::Utc(_)));
+        assert_eq!(time.to_der_vec(), buffer);
+
+        let buffer = encode("20500101000000Z");
+        let time = Time::deserialize(&mut &buffer[..]).unwrap();
+        assert!(matches!(time, Time::Generalized(_)));
+        assert_eq!(time.to_der_vec(), buffer);
+
+        assert!(matches!(
+            Time::deserialize(&mut &[0x04, 0x00][..]),
+            Err(DerError::UnexpectedTag { .. })
+        ));
+
+        // RFC 5280 switches to GeneralizedTime from 2050
+        assert_eq!(
+            Time::new(2049, 12, 31, 23, 59, 59).unwrap(),
+            Time::Utc(UtcTime::new(2049, 12, 31, 23, 59, 59).unwrap())
+        );
+        assert_eq!(
+            Time::new(2050, 1, 1, 0, 0, 0).unwrap(),
+            Time::Generalized(GeneralizedTime::new(2050, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert!(matches!(
+            Time::new(1949, 12, 31, 0, 0, 0).unwrap(),
+            Time::Generalized(_)
+        ));
+    }
+
+    #[test]
+    fn unix_time_conversions() {
+        let time = GeneralizedTime::from_unix_seconds(1_609_459_200).unwrap();
+        assert_eq!(time.to_der_vec(), encode("20210101000000Z"));
+        assert_eq!(
+            UtcTime::from_unix_seconds(1_609_459_200)
+                .unwrap()
+                .to_der_vec()[2..],
+            *b"210101000000Z"
+        );
+
+        assert_eq!(
+            GeneralizedTime::from_unix_seconds(0).unwrap().to_der_vec(),
+            encode("19700101000000Z")
+        );
+        assert_eq!(
+            GeneralizedTime::from_unix_seconds(-1).unwrap().to_der_vec(),
+            encode("19691231235959Z")
+        );
+        assert_eq!(
+            GeneralizedTime::from_unix_seconds(951_868_799)
+                .unwrap()
+                .to_der_vec(),
+            encode("20000229235959Z")
+        );
+        assert_eq!(
+            GeneralizedTime::from_unix_seconds(253_402_300_799)
+                .unwrap()
+                .to_der_vec(),
+            encode("99991231235959Z")
+        );
+        assert!(GeneralizedTime::from_unix_seconds(253_402_300_800).is_err());
+
+        // 2050-01-01 is past the UTCTime range
+        assert!(UtcTime::from_unix_seconds(2_524_608_000).is_err());
+        assert!(matches!(
+            Time::from_unix_seconds(2_524_608_000).unwrap(),
+            Time::Generalized(_)
+        ));
+        assert!(matches!(
+            Time::from_unix_seconds(1_609_459_200).unwrap(),
+            Time::Utc(_)
+        ));
+
+        let system = UNIX_EPOCH + std::time::Duration::new(1_609_459_200, 500_000_000);
+        assert_eq!(
+            GeneralizedTime::from_system_time(system)
+                .unwrap()
+                .to_der_vec(),
+            encode("20210101000000.5Z")
+        );
+        assert_eq!(
+            Time::from_system_time(system).unwrap(),
+            Time::from_unix_seconds(1_609_459_200).unwrap()
+        );
+
+        let before = UNIX_EPOCH - std::time::Duration::from_millis(250);
+        let time = GeneralizedTime::from_system_time(before).unwrap();
+        assert_eq!(time.to_der_vec(), encode("19691231235959.75Z"));
+    }
+}
@@ -0,0 +1,381 @@
+use crate::{
+    explicit_tag, read_contents, read_explicit, set_of::set_of_order, tlv_len, write_header,
+    AnyTlv, BigInt, BitString, DerDeserialize, DerError, DerSerialize, GeneralizedTime, Ia5String,
+    Null, ObjectIdentifier, OctetString, PrintableString, UtcTime, Utf8String, BIT_STRING, BOOLEAN,
+    GENERALIZED_TIME, IA5_STRING, INTEGER, MAX_DEPTH, NULL, OBJECT_IDENTIFIER, OCTET_STRING,
+    PRINTABLE_STRING, SEQUENCE, SET, UTC_TIME, UTF8_STRING,
+};
+use std::io::{self, Write};
+
+/// A dynamically typed DER value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Value {
+    Bool(bool),
+    Integer(BigInt),
+    BitString(BitString),
+    OctetString(Vec<u8>),
+    Null,
+    Oid(ObjectIdentifier),
+    Utf8(String),
+    Printable(PrintableString),
+    Ia5(Ia5String),
+    UtcTime(UtcTime),
+    GeneralizedTime(GeneralizedTime),
+    Sequence(Vec<Value>),
+    Set(Vec<Value>),
+    /// An `[N] EXPLICIT` wrapper around another value. Serializing fails if
+    /// `N` is 31 or above.
+    ContextTagged(u8, Box<Value>),
+    /// An OPTIONAL component that wasn't present
+    Absent,
+    /// A TLV that wasn't interpreted
+    Other(AnyTlv),
+}
+
+/// Decodes a single value without a schema, which must span all of `bytes`.
+///
+/// The universal types `Value` has a variant for are parsed, recursing into
+/// SEQUENCEs and SETs, and a constructed context-specific tag holding exactly
+/// one element is treated as an explicit tag. Everything else, such as an
+/// EXTERNAL or an application tag, is kept as `Value::Other`. Values nested
+/// more than `MAX_DEPTH` deep are rejected as `DerError::InvalidEncoding`.
+pub fn decode_value(bytes: &[u8]) -> Result<Value, DerError> {
+    decode_value_with(bytes, |tlv| Ok(Value::Other(tlv)))
+}
+
+/// Like `decode_value`, but each TLV that would be kept as `Value::Other` is
+/// passed to `on_other` instead, to interpret types `Value` doesn't model.
+pub fn decode_value_with<F>(bytes: &[u8], mut on_other: F) -> Result<Value, DerError>
+where
+    F: FnMut(AnyTlv) -> Result<Value, DerError>,
+{
+    let mut bytes = bytes;
+    let value = decode(&mut bytes, &mut on_other, 0)?;
+
+    if !bytes.is_empty() {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    Ok(value)
+}
+
+type OnOther<'a> = dyn FnMut(AnyTlv) -> Result<Value, DerError> + 'a;
+
+// `depth` counts the constructed values the next one is inside of
+fn decode(bytes: &mut &[u8], on_other: &mut OnOther<'_>, depth: usize) -> Result<Value, DerError> {
+    let tag = *bytes.first().ok_or(DerError::UnexpectedEof)?;
+
+    Ok(match tag {
+        BOOLEAN => Value::Bool(bool::deserialize(bytes)?),
+        INTEGER => Value::Integer(BigInt::deserialize(bytes)?),
+        BIT_STRING => Value::BitString(BitString::deserialize(bytes)?),
+        OCTET_STRING => Value::OctetString(OctetString::deserialize(bytes)?.into_inner()),
+        NULL => {
+            Null::deserialize(bytes)?;
+            Value::Null
+        }
+        OBJECT_IDENTIFIER => Value::Oid(ObjectIdentifier::deserialize(bytes)?),
+        UTF8_STRING => Value::Utf8(Utf8String::deserialize(bytes)?.into_string()),
+        PRINTABLE_STRING => Value::Printable(PrintableString::deserialize(bytes)?),
+        IA5_STRING => Value::Ia5(Ia5String::deserialize(bytes)?),
+        UTC_TIME => Value::UtcTime(UtcTime::deserialize(bytes)?),
+        GENERALIZED_TIME => Value::GeneralizedTime(GeneralizedTime::deserialize(bytes)?),
+        SEQUENCE => Value::Sequence(decode_elements(
+            read_contents(bytes, SEQUENCE)?,
+            on_other,
+            depth,
+        )?),
+        SET => Value::Set(decode_elements(
+            read_contents(bytes, SET)?,
+            on_other,
+            depth,
+        )?),
+        // Constructed, context-specific, low tag number
+        _ if tag & 0b1110_0000 == 0b1010_0000 && tag & 0b0001_1111 != 0b0001_1111 => {
+            let start = *bytes;
+            let number = tag & 0b0001_1111;
+            let mut elements = decode_elements(read_explicit(bytes, number)?, on_other, depth)?;
+
+            match elements.pop() {
+                Some(inner) if elements.is_empty() => Value::ContextTagged(number, Box::new(inner)),
+                _ => {
+                    *bytes = start;
+                    on_other(AnyTlv::deserialize(bytes)?)?
+                }
+            }
+        }
+        _ => on_other(AnyTlv::deserialize(bytes)?)?,
+    })
+}
+
+// Decodes the elements of a constructed value that is `depth` deep
+fn decode_elements(
+    mut contents: &[u8],
+    on_other: &mut OnOther<'_>,
+    depth: usize,
+) -> Result<Vec<Value>, DerError> {
+    if depth == MAX_DEPTH {
+        return Err(DerError::InvalidEncoding);
+    }
+
+    let mut values = Vec::new();
+
+    while !contents.is_empty() {
+        values.push(decode(&mut contents, on_other, depth + 1)?);
+    }
+
+    Ok(values)
+}
+
+// Writes `bytes` as the contents of a primitive value
+fn write_primitive<W: Write + ?Sized>(tag: u8, bytes: &[u8], writer: &mut W) -> io::Result<usize> {
+    let written = write_header(tag, bytes.len(), writer)?;
+    writer.write_all(bytes)?;
+
+    Ok(written + bytes.len())
+}
+
+// Each variant is written with its type's DER encoding. SET elements are
+// sorted by their encodings, so a `Value::Set` is always written in SET OF
+// order.
+impl DerSerialize for Value {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        match self {
+            Value::Bool(value) => value.serialize(writer),
+            Value::Integer(value) => value.serialize(writer),
+            Value::BitString(value) => value.serialize(writer),
+            Value::OctetString(bytes) => write_primitive(OCTET_STRING, bytes, writer),
+            Value::Null => Null.serialize(writer),
+            Value::Oid(oid) => oid.serialize(writer),
+            Value::Utf8(s) => write_primitive(UTF8_STRING, s.as_bytes(), writer),
+            Value::Printable(s) => s.serialize(writer),
+            Value::Ia5(s) => s.serialize(writer),
+            Value::UtcTime(time) => time.serialize(writer),
+            Value::GeneralizedTime(time) => time.serialize(writer),
+            Value::Sequence(values) => {
+                let content_len = values.iter().map(DerSerialize::encoded_len).sum();
+                let mut written = write_header(SEQUENCE, content_len, writer)?;
+
+                for value in values {
+                    written += value.serialize(writer)?;
+                }
+
+                Ok(written)
+            }
+            Value::Set(values) => {
+                let mut encodings = values
+                    .iter()
+                    .map(|value| {
+                        let mut encoding = Vec::with_capacity(value.encoded_len());
+                        value.serialize(&mut encoding)?;
+
+                        Ok(encoding)
+                    })
+                    .collect::<io::Result<Vec<_>>>()?;
+                encodings.sort_by(|a, b| set_of_order(a, b));
+
+                let content_len = encodings.iter().map(Vec::len).sum();
+                let written = write_header(SET, content_len, writer)?;
+
+                for encoding in &encodings {
+                    writer.write_all(encoding)?;
+                }
+
+                Ok(written + content_len)
+            }
+            Value::ContextTagged(number, _) if *number >= 31 => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "high tag numbers aren't supported for explicit tags",
+            )),
+            Value::ContextTagged(number, inner) => {
+                let written = write_header(explicit_tag(*number), inner.encoded_len(), writer)?;
+
+                Ok(written + inner.serialize(writer)?)
+            }
+            Value::Absent => Ok(0),
+            Value::Other(tlv) => tlv.serialize(writer),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            Value::Sequence(values) | Value::Set(values) => {
+                tlv_len(values.iter().map(DerSerialize::encoded_len).sum())
+            }
+            Value::ContextTagged(_, inner) => tlv_len(inner.encoded_len()),
+            Value::Absent => 0,
+            // The remaining variants always serialize
+            value => value
+                .serialize(&mut io::sink())
+                .expect("serializing into a sink never fails"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A self-signed P-256 certificate with keyUsage and subjectAltName
+    // extensions
+    const CERTIFICATE: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIB5DCCAYmgAwIBAgIUM5a3faR3Qz9E28EPAIk7k6b+ef8wCgYIKoZIzj0EAwIw
+MzELMAkGA1UEBhMCVVMxDzANBgNVBAoMBlNlcmRlcjETMBEGA1UEAwwKVmFsdWUg
+VGVzdDAeFw0yNjEwMTQxMDE2NTFaFw0zNjEwMTExMDE2NTFaMDMxCzAJBgNVBAYT
+AlVTMQ8wDQYDVQQKDAZTZXJkZXIxEzARBgNVBAMMClZhbHVlIFRlc3QwWTATBgcq
+hkjOPQIBBggqhkjOPQMBBwNCAAQYeGc7i0fINIrOiLZPt9wYXncdKXfG1YBr6hdM
+xz+1YbGfmZIvNlbpBHev3CxH32LyXFf6i4ZuKdT6cfBBYCAxo3sweTAdBgNVHQ4E
+FgQUjiaECKhOMRigO0QjEA3RgMjHoBcwHwYDVR0jBBgwFoAUjiaECKhOMRigO0Qj
+EA3RgMjHoBcwDwYDVR0TAQH/BAUwAwEB/zAOBgNVHQ8BAf8EBAMCAoQwFgYDVR0R
+BA8wDYILZXhhbXBsZS5jb20wCgYIKoZIzj0EAwIDSQAwRgIhAOkwJZjkxNtJ5vYM
+1I65b/hCAZ181yEjK3k4xYBVbgFiAiEA1CKo4L/rI/FftXVsU+p0I3caz+rj2o++
+BbAcmYbFrb4=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn decode_mixed_sequence() {
+        let buffer = [
+            0x30, 0x20, // SEQUENCE
+            0x02, 0x01, 0x05, // INTEGER 5
+            0x01, 0x01, 0xFF, // BOOLEAN TRUE
+            0x04, 0x02, 0x01, 0x02, // OCTET STRING
+            0x30, 0x0B, 0x06, 0x07, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01, 0x05, 0x00, 0xA0,
+            0x04, 0x0C, 0x02, b'h', b'i', // [0] EXPLICIT UTF8String
+            0x0A, 0x01, 0x01, // ENUMERATED
+        ];
+
+        assert_eq!(
+            decode_value(&buffer).unwrap(),
+            Value::Sequence(vec![
+                Value::Integer(BigInt::from_unsigned_bytes_be(&[5])),
+                Value::Bool(true),
+                Value::OctetString(vec![1, 2]),
+                Value::Sequence(vec![
+                    Value::Oid(ObjectIdentifier::from_bytes(crate::oids::EC_PUBLIC_KEY).unwrap()),
+                    Value::Null,
+                ]),
+                Value::ContextTagged(0, Box::new(Value::Utf8(String::from("hi")))),
+                Value::Other(AnyTlv::new(0x0A, &[0x01])),
+            ])
+        );
+
+        // A constructed context tag that isn't a single element
+        let buffer = [0xA1, 0x06, 0x05, 0x00, 0x05, 0x00, 0x05, 0x00];
+        assert!(matches!(decode_value(&buffer).unwrap(), Value::Other(_)));
+
+        // Known types are validated
+        assert!(decode_value(&[0x01, 0x01, 0x01]).is_err());
+        assert!(decode_value(&[0x30, 0x03, 0x02, 0x01]).is_err());
+        assert!(decode_value(&[0x05, 0x00, 0x05, 0x00]).is_err());
+    }
+
+    #[test]
+    fn decode_unmodeled_types() {
+        // EXTERNAL, EMBEDDED PDV and an application tag
+        let buffer = [
+            0x30, 0x0D, 0x28, 0x03, 0x02, 0x01, 0x01, 0x2B, 0x02, 0x05, 0x00, 0x61, 0x02, 0x05,
+            0x00,
+        ];
+        assert_eq!(
+            decode_value(&buffer).unwrap(),
+            Value::Sequence(vec![
+                Value::Other(AnyTlv::new(0x28, &[0x02, 0x01, 0x01])),
+                Value::Other(AnyTlv::new(0x2B, &[0x05, 0x00])),
+                Value::Other(AnyTlv::new(0x61, &[0x05, 0x00])),
+            ])
+        );
+
+        // ENUMERATED read as an INTEGER, everything else rejected
+        let value = decode_value_with(&[0x30, 0x03, 0x0A, 0x01, 0x02], |tlv| match tlv.tag() {
+            0x0A => Ok(Value::Integer(BigInt::from_signed_bytes_be(tlv.contents()))),
+            _ => Err(DerError::InvalidEncoding),
+        });
+        assert_eq!(
+            value.unwrap(),
+            Value::Sequence(vec![Value::Integer(BigInt::from_unsigned_bytes_be(&[2]))])
+        );
+        assert!(decode_value_with(&buffer, |_| Err(DerError::InvalidEncoding)).is_err());
+    }
+
+    #[test]
+    fn decode_nesting_limit() {
+        // `depth` definite length SEQUENCEs, one inside the next
+        let nested = |depth: usize| {
+            let mut lengths = vec![0];
+            for _ in 1..depth {
+                lengths.push(tlv_len(*lengths.last().unwrap()));
+            }
+
+            let mut der = Vec::new();
+            for &length in lengths.iter().rev() {
+                write_header(SEQUENCE, length, &mut der).unwrap();
+            }
+
+            der
+        };
+
+        let der = nested(MAX_DEPTH);
+        assert_eq!(decode_value(&der).unwrap().to_der_vec(), der);
+
+        for depth in [MAX_DEPTH + 1, 200_000] {
+            assert!(matches!(
+                decode_value(&nested(depth)),
+                Err(DerError::InvalidEncoding)
+            ));
+        }
+    }
+
+    #[test]
+    fn certificate_round_trip() {
+        let (_, der) = crate::pem::from_pem(CERTIFICATE).unwrap();
+        assert_eq!(der.len(), 488);
+
+        let value = decode_value(&der).unwrap();
+        assert_eq!(value.encoded_len(), der.len());
+        assert_eq!(value.to_der_vec(), der);
+
+        // tbsCertificate starts with an explicit [0] version
+        match &value {
+            Value::Sequence(certificate) => match &certificate[0] {
+                Value::Sequence(tbs) => assert!(matches!(tbs[0], Value::ContextTagged(0, _))),
+                other => panic!("unexpected tbsCertificate {:?}", other),
+            },
+            other => panic!("unexpected certificate {:?}", other),
+        }
+    }
+
+    #[test]
+    fn encode_built_values() {
+        let value = Value::Sequence(vec![
+            Value::Set(vec![
+                Value::Integer(BigInt::from_unsigned_bytes_be(&[0x02])),
+                Value::Integer(BigInt::from_unsigned_bytes_be(&[0x01])),
+            ]),
+            Value::Absent,
+            Value::ContextTagged(1, Box::new(Value::Null)),
+            Value::OctetString(vec![0xAB]),
+        ]);
+
+        let encoding = [
+            0x30, 0x0F, 0x31, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02, 0xA1, 0x02, 0x05, 0x00,
+            0x04, 0x01, 0xAB,
+        ];
+        assert_eq!(value.to_der_vec(), encoding);
+        assert_eq!(value.encoded_len(), encoding.len());
+
+        // Strings are checked when the value is built, not when it's written
+        assert!(PrintableString::new("a@b").is_err());
+        assert_eq!(
+            Value::Sequence(vec![Value::Printable(PrintableString::new("a b").unwrap())])
+                .to_der_vec(),
+            [0x30, 0x05, 0x13, 0x03, 0x61, 0x20, 0x62]
+        );
+        let high_tag = Value::ContextTagged(40, Box::new(Value::Null));
+        assert!(high_tag.serialize(&mut io::sink()).is_err());
+        assert!(Value::Set(vec![high_tag])
+            .serialize(&mut io::sink())
+            .is_err());
+    }
+}
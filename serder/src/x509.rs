@@ -0,0 +1,885 @@
+//! Types from the X.509 certificate profile in RFC 5280.
+
+use crate::{
+    context, read_contents, write_header, AnyTlv, BigInt, BitString, Captured, DerDeserialize,
+    DerError, DerSerialize, Explicit, Ia5String, ObjectIdentifier, OctetString, PrintableString,
+    SetOf, SizeBounded, Tag, Time, Utf8String,
+};
+use std::io::{self, Write};
+
+// id-at-commonName, 2.5.4.3
+const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// `AttributeTypeAndValue ::= SEQUENCE { type OID, value ANY }`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct AttributeTypeAndValue {
+    pub attribute_type: ObjectIdentifier,
+    pub value: AnyTlv,
+}
+
+impl AttributeTypeAndValue {
+    /// The value as a string, if it's one of the string types used for
+    /// directory names.
+    pub fn value_str(&self) -> Option<String> {
+        match self.value.tag() {
+            crate::UTF8_STRING => self.value.parse().ok().map(Utf8String::into_string),
+            crate::PRINTABLE_STRING => self.value.parse().ok().map(PrintableString::into_string),
+            crate::IA5_STRING => self.value.parse().ok().map(Ia5String::into_string),
+            _ => None,
+        }
+    }
+}
+
+/// `RelativeDistinguishedName ::= SET OF AttributeTypeAndValue`
+pub type RelativeDistinguishedName = SetOf<AttributeTypeAndValue>;
+
+/// `RDNSequence ::= SEQUENCE OF RelativeDistinguishedName`, the form of a
+/// certificate's subject and issuer names.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+#[der(transparent)]
+pub struct RdnSequence(pub Vec<RelativeDistinguishedName>);
+
+impl RdnSequence {
+    pub fn attributes(&self) -> impl Iterator<Item = &AttributeTypeAndValue> {
+        self.0.iter().flat_map(|rdn| rdn.iter())
+    }
+
+    /// The first common name (CN) attribute with a string value.
+    pub fn common_name(&self) -> Option<String> {
+        self.attributes()
+            .filter(|attribute| attribute.attribute_type.as_bytes() == COMMON_NAME)
+            .find_map(AttributeTypeAndValue::value_str)
+    }
+}
+
+/// `Extension ::= SEQUENCE { extnID OID, critical BOOLEAN DEFAULT FALSE,
+/// extnValue OCTET STRING }`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct Extension {
+    pub extn_id: ObjectIdentifier,
+    #[der(default)]
+    pub critical: bool,
+    /// DER encoding of the extension's own value
+    pub extn_value: OctetString,
+}
+
+/// `Extensions ::= SEQUENCE SIZE (1..MAX) OF Extension`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+#[der(transparent)]
+pub struct Extensions(pub SizeBounded<Vec<Extension>, 1, { usize::MAX }>);
+
+impl Extensions {
+    /// Fails with `DerError::SizeOutOfRange` if `extensions` is empty.
+    pub fn new(extensions: Vec<Extension>) -> Result<Self, DerError> {
+        Ok(Extensions(SizeBounded::new(extensions)?))
+    }
+
+    /// The extension with the given ID, if present.
+    pub fn get(&self, extn_id: &ObjectIdentifier) -> Option<&Extension> {
+        self.0
+            .get()
+            .iter()
+            .find(|extension| extension.extn_id == *extn_id)
+    }
+}
+
+/// `Attribute ::= SEQUENCE { type OID, values SET OF ANY }`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct Attribute {
+    pub attr_type: ObjectIdentifier,
+    pub values: SetOf<AnyTlv>,
+}
+
+impl Attribute {
+    /// Builds an attribute, sorting `values` into DER order.
+    pub fn new(attr_type: ObjectIdentifier, values: Vec<AnyTlv>) -> Self {
+        Attribute {
+            attr_type,
+            values: SetOf::new(values),
+        }
+    }
+}
+
+/// `Attributes ::= SET OF Attribute`, as carried in PKCS #7 signed
+/// attributes, PKCS #10 requests and PKCS #12 bags.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+#[der(transparent)]
+pub struct AttributeSet(pub SetOf<Attribute>);
+
+impl AttributeSet {
+    /// Builds a set, sorting `attributes` into DER order.
+    pub fn new(attributes: Vec<Attribute>) -> Self {
+        AttributeSet(SetOf::new(attributes))
+    }
+
+    /// The attribute with the given type, if present.
+    pub fn get(&self, attr_type: &ObjectIdentifier) -> Option<&Attribute> {
+        self.0
+            .iter()
+            .find(|attribute| attribute.attr_type == *attr_type)
+    }
+}
+
+/// `ECDSA-Sig-Value ::= SEQUENCE { r INTEGER, s INTEGER }` from RFC 3279, the
+/// encoding of an ECDSA signature in certificates and TLS.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct EcdsaSigValue {
+    pub r: BigInt,
+    pub s: BigInt,
+}
+
+impl EcdsaSigValue {
+    /// Builds a signature from big-endian `r` and `s` scalars.
+    pub fn from_scalars(r: &[u8], s: &[u8]) -> Self {
+        EcdsaSigValue {
+            r: BigInt::from_unsigned_bytes_be(r),
+            s: BigInt::from_unsigned_bytes_be(s),
+        }
+    }
+
+    /// `r` as an `N` byte big-endian scalar, 32 for P-256. `None` if it's
+    /// negative or too large for the curve size.
+    pub fn r_bytes<const N: usize>(&self) -> Option<[u8; N]> {
+        self.r.to_fixed_bytes_be()
+    }
+
+    /// `s` as an `N` byte big-endian scalar, 32 for P-256. `None` if it's
+    /// negative or too large for the curve size.
+    pub fn s_bytes<const N: usize>(&self) -> Option<[u8; N]> {
+        self.s.to_fixed_bytes_be()
+    }
+}
+
+/// `RSAPublicKey ::= SEQUENCE { modulus INTEGER, publicExponent INTEGER }`
+/// from PKCS #1 (RFC 8017), the key inside an RSA `SubjectPublicKeyInfo`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct RsaPublicKey {
+    pub modulus: BigInt,
+    pub public_exponent: BigInt,
+}
+
+impl RsaPublicKey {
+    /// Builds a key from a big-endian modulus and exponent, adding the sign
+    /// pad where the top bit is set.
+    pub fn from_components(modulus: &[u8], public_exponent: &[u8]) -> Self {
+        RsaPublicKey {
+            modulus: BigInt::from_unsigned_bytes_be(modulus),
+            public_exponent: BigInt::from_unsigned_bytes_be(public_exponent),
+        }
+    }
+
+    /// The key size: the bit length of the modulus. `None` if the modulus is
+    /// negative.
+    pub fn bits(&self) -> Option<usize> {
+        let modulus = self.modulus.to_unsigned_bytes_be()?;
+        let leading_zeros = modulus[0].leading_zeros() as usize;
+
+        Some(modulus.len() * 8 - leading_zeros)
+    }
+
+    /// The modulus as an `N` byte big-endian number, 256 for a 2048-bit key.
+    /// `None` if it's negative or doesn't fit.
+    pub fn modulus_bytes<const N: usize>(&self) -> Option<[u8; N]> {
+        self.modulus.to_fixed_bytes_be()
+    }
+}
+
+/// `AlgorithmIdentifier ::= SEQUENCE { algorithm OID, parameters ANY
+/// OPTIONAL }`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct AlgorithmIdentifier {
+    pub algorithm: ObjectIdentifier,
+    pub parameters: Option<AnyTlv>,
+}
+
+/// `SubjectPublicKeyInfo ::= SEQUENCE { algorithm AlgorithmIdentifier,
+/// subjectPublicKey BIT STRING }`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct SubjectPublicKeyInfo {
+    pub algorithm: AlgorithmIdentifier,
+    /// DER encoding of the key itself for RSA, the curve point for EC keys
+    pub subject_public_key: BitString,
+}
+
+/// `Validity ::= SEQUENCE { notBefore Time, notAfter Time }`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct Validity {
+    pub not_before: Time,
+    pub not_after: Time,
+}
+
+/// `Version ::= INTEGER { v1(0), v2(1), v3(2) }`
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, DerSerialize, DerDeserialize,
+)]
+#[der(integer)]
+pub enum Version {
+    #[default]
+    V1 = 0,
+    V2 = 1,
+    V3 = 2,
+}
+
+/// `[N] IMPLICIT UniqueIdentifier`, where `UniqueIdentifier ::= BIT STRING`.
+/// `N` must be below 31.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UniqueIdentifier<const N: u8>(pub BitString);
+
+impl<const N: u8> UniqueIdentifier<N> {
+    const TAG: u8 = {
+        assert!(
+            N < 31,
+            "high tag numbers aren't supported for implicit tags"
+        );
+        Tag::new(N).context_specific().primitive().into_tag_value()
+    };
+}
+
+impl<const N: u8> DerSerialize for UniqueIdentifier<N> {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
+        let bytes = self.0.as_bytes();
+        let written = write_header(Self::TAG, 1 + bytes.len(), writer)?;
+        writer.write_all(&[self.0.unused_bits()])?;
+        writer.write_all(bytes)?;
+
+        Ok(written + 1 + bytes.len())
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl<const N: u8> DerDeserialize for UniqueIdentifier<N> {
+    fn deserialize(bytes: &mut &[u8]) -> Result<Self, DerError> {
+        match read_contents(bytes, Self::TAG)?.split_first() {
+            Some((unused_bits, bytes)) => {
+                Ok(UniqueIdentifier(BitString::new(bytes, *unused_bits)?))
+            }
+            None => Err(DerError::InvalidEncoding),
+        }
+    }
+
+    fn matches_tag(tag: u8) -> bool {
+        tag == Self::TAG
+    }
+}
+
+/// The `TBSCertificate` SEQUENCE, the signed part of a certificate. RFC 5280
+/// says CAs must not generate the issuer and subject unique identifiers, but
+/// that applications should be able to parse them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct TbsCertificate {
+    /// `[0] EXPLICIT Version DEFAULT v1`
+    #[der(default)]
+    pub version: Explicit<0, Version>,
+    pub serial_number: BigInt,
+    pub signature: AlgorithmIdentifier,
+    pub issuer: RdnSequence,
+    pub validity: Validity,
+    pub subject: RdnSequence,
+    pub subject_public_key_info: SubjectPublicKeyInfo,
+    /// `[1] IMPLICIT UniqueIdentifier OPTIONAL`
+    pub issuer_unique_id: Option<UniqueIdentifier<1>>,
+    /// `[2] IMPLICIT UniqueIdentifier OPTIONAL`
+    pub subject_unique_id: Option<UniqueIdentifier<2>>,
+    pub extensions: Option<Explicit<3, Extensions>>,
+}
+
+/// `Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm,
+/// signatureValue BIT STRING }`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, DerSerialize, DerDeserialize)]
+pub struct Certificate {
+    /// Kept with its original encoding, which the signature covers even when
+    /// it isn't quite DER
+    pub tbs_certificate: Captured<TbsCertificate>,
+    pub signature_algorithm: AlgorithmIdentifier,
+    pub signature_value: BitString,
+}
+
+/// Assembles a `TbsCertificate` one field at a time. The version is picked
+/// from the contents: v3 when there are extensions, otherwise v1.
+///
+/// Signing is left to the caller: sign the DER of the built
+/// `TbsCertificate`, then put it in a `Certificate` with the signature,
+/// using `Captured::new` to keep both from the same encoding.
+#[derive(Clone, Debug, Default)]
+pub struct CertificateBuilder {
+    serial_number: Option<BigInt>,
+    signature: Option<AlgorithmIdentifier>,
+    issuer: Option<RdnSequence>,
+    validity: Option<Validity>,
+    subject: Option<RdnSequence>,
+    subject_public_key_info: Option<SubjectPublicKeyInfo>,
+    extensions: Vec<Extension>,
+}
+
+impl CertificateBuilder {
+    pub fn new() -> Self {
+        CertificateBuilder::default()
+    }
+
+    pub fn serial_number(mut self, serial_number: BigInt) -> Self {
+        self.serial_number = Some(serial_number);
+        self
+    }
+
+    /// The algorithm the certificate will be signed with.
+    pub fn signature_algorithm(mut self, algorithm: AlgorithmIdentifier) -> Self {
+        self.signature = Some(algorithm);
+        self
+    }
+
+    pub fn issuer(mut self, issuer: RdnSequence) -> Self {
+        self.issuer = Some(issuer);
+        self
+    }
+
+    pub fn validity(mut self, not_before: Time, not_after: Time) -> Self {
+        self.validity = Some(Validity {
+            not_before,
+            not_after,
+        });
+        self
+    }
+
+    pub fn subject(mut self, subject: RdnSequence) -> Self {
+        self.subject = Some(subject);
+        self
+    }
+
+    pub fn subject_public_key_info(mut self, info: SubjectPublicKeyInfo) -> Self {
+        self.subject_public_key_info = Some(info);
+        self
+    }
+
+    /// Adds an extension after any added before it.
+    pub fn extension(mut self, extension: Extension) -> Self {
+        self.extensions.push(extension);
+        self
+    }
+
+    /// Fails with `DerError::UnexpectedEof`, annotated with the field's
+    /// name, if a field other than the extensions wasn't set.
+    pub fn build(self) -> Result<Vec<u8>, DerError> {
+        Ok(self.build_tbs()?.to_der_vec())
+    }
+
+    /// Like `build`, but returns the typed `TbsCertificate`.
+    pub fn build_tbs(self) -> Result<TbsCertificate, DerError> {
+        fn required<T>(value: Option<T>, field: &'static str) -> Result<T, DerError> {
+            context(value.ok_or(DerError::UnexpectedEof), || field)
+        }
+
+        let (version, extensions) = match self.extensions.len() {
+            0 => (Version::V1, None),
+            _ => (
+                Version::V3,
+                Some(Explicit(Extensions::new(self.extensions)?)),
+            ),
+        };
+
+        Ok(TbsCertificate {
+            version: Explicit(version),
+            serial_number: required(
+                self.serial_number,
+                "while building TBSCertificate.serialNumber",
+            )?,
+            signature: required(self.signature, "while building TBSCertificate.signature")?,
+            issuer: required(self.issuer, "while building TBSCertificate.issuer")?,
+            validity: required(self.validity, "while building TBSCertificate.validity")?,
+            subject: required(self.subject, "while building TBSCertificate.subject")?,
+            subject_public_key_info: required(
+                self.subject_public_key_info,
+                "while building TBSCertificate.subjectPublicKeyInfo",
+            )?,
+            issuer_unique_id: None,
+            subject_unique_id: None,
+            extensions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DerError;
+
+    // Subject of the ISRG Root X1 certificate,
+    // CN=ISRG Root X1, O=Internet Security Research Group, C=US
+    const ISRG_ROOT_X1_SUBJECT: &[u8] = &[
+        0x30, 0x4F, 0x31, 0x0B, 0x30, 0x09, 0x06, 0x03, 0x55, 0x04, 0x06, 0x13, 0x02, 0x55, 0x53,
+        0x31, 0x29, 0x30, 0x27, 0x06, 0x03, 0x55, 0x04, 0x0A, 0x13, 0x20, 0x49, 0x6E, 0x74, 0x65,
+        0x72, 0x6E, 0x65, 0x74, 0x20, 0x53, 0x65, 0x63, 0x75, 0x72, 0x69, 0x74, 0x79, 0x20, 0x52,
+        0x65, 0x73, 0x65, 0x61, 0x72, 0x63, 0x68, 0x20, 0x47, 0x72, 0x6F, 0x75, 0x70, 0x31, 0x15,
+        0x30, 0x13, 0x06, 0x03, 0x55, 0x04, 0x03, 0x13, 0x0C, 0x49, 0x53, 0x52, 0x47, 0x20, 0x52,
+        0x6F, 0x6F, 0x74, 0x20, 0x58, 0x31,
+    ];
+
+    #[test]
+    fn rdn_sequence_common_name() {
+        let name = RdnSequence::deserialize(&mut &ISRG_ROOT_X1_SUBJECT[..]).unwrap();
+
+        assert_eq!(name.0.len(), 3);
+        assert_eq!(name.common_name().as_deref(), Some("ISRG Root X1"));
+
+        let organization = name.attributes().nth(1).unwrap();
+        assert_eq!(organization.attribute_type.to_string(), "2.5.4.10");
+        assert_eq!(
+            organization.value_str().as_deref(),
+            Some("Internet Security Research Group")
+        );
+
+        assert_eq!(name.to_der_vec(), ISRG_ROOT_X1_SUBJECT);
+    }
+
+    #[test]
+    fn rdn_sequence_without_common_name() {
+        let name = RdnSequence(vec![SetOf::new(vec![AttributeTypeAndValue {
+            attribute_type: "2.5.4.6".parse().unwrap(),
+            value: AnyTlv::new(crate::PRINTABLE_STRING, b"US"),
+        }])]);
+
+        let buffer = name.to_der_vec();
+        let decoded = RdnSequence::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(decoded, name);
+        assert_eq!(decoded.common_name(), None);
+    }
+
+    // basicConstraints (2.5.29.19) with cA TRUE
+    const BASIC_CONSTRAINTS_CRITICAL: &[u8] = &[
+        0x30, 0x0F, 0x06, 0x03, 0x55, 0x1D, 0x13, 0x01, 0x01, 0xFF, 0x04, 0x05, 0x30, 0x03, 0x01,
+        0x01, 0xFF,
+    ];
+
+    #[test]
+    fn extension_critical_flag() {
+        let extension = Extension::deserialize(&mut &BASIC_CONSTRAINTS_CRITICAL[..]).unwrap();
+        assert_eq!(extension.extn_id.to_string(), "2.5.29.19");
+        assert!(extension.critical);
+        assert_eq!(
+            extension.extn_value.as_bytes(),
+            &[0x30, 0x03, 0x01, 0x01, 0xFF]
+        );
+        assert_eq!(extension.to_der_vec(), BASIC_CONSTRAINTS_CRITICAL);
+
+        // Absent critical flag means FALSE
+        let non_critical = [
+            0x30, 0x0C, 0x06, 0x03, 0x55, 0x1D, 0x13, 0x04, 0x05, 0x30, 0x03, 0x01, 0x01, 0xFF,
+        ];
+        let extension = Extension::deserialize(&mut &non_critical[..]).unwrap();
+        assert!(!extension.critical);
+        assert_eq!(extension.to_der_vec(), non_critical);
+
+        // DER forbids encoding the DEFAULT value
+        let explicit_false = [
+            0x30, 0x0F, 0x06, 0x03, 0x55, 0x1D, 0x13, 0x01, 0x01, 0x00, 0x04, 0x05, 0x30, 0x03,
+            0x01, 0x01, 0xFF,
+        ];
+        assert!(matches!(
+            Extension::deserialize(&mut &explicit_false[..])
+                .unwrap_err()
+                .root_cause(),
+            DerError::InvalidEncoding
+        ));
+
+        let extensions = Extensions::new(vec![extension]).unwrap();
+        let buffer = extensions.to_der_vec();
+        let decoded = Extensions::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(decoded, extensions);
+        assert!(decoded.get(&"2.5.29.19".parse().unwrap()).is_some());
+
+        // SIZE (1..MAX)
+        assert!(matches!(
+            Extensions::deserialize(&mut &[0x30, 0x00][..]),
+            Err(DerError::SizeOutOfRange { size: 0, .. })
+        ));
+        assert!(Extensions::new(vec![]).is_err());
+    }
+
+    // A P-256 ECDSA signature over "hello" from openssl
+    const ECDSA_SIGNATURE: &[u8] = &[
+        0x30, 0x45, 0x02, 0x20, 0x11, 0x8F, 0xA5, 0x6F, 0xEC, 0x31, 0x59, 0x5D, 0x6A, 0x31, 0x4B,
+        0x0A, 0xAB, 0x9A, 0x28, 0x43, 0x07, 0xBA, 0x0B, 0x12, 0x4B, 0x41, 0xB7, 0xCF, 0xAF, 0x9A,
+        0xC3, 0x2F, 0x70, 0x7C, 0xC4, 0xED, 0x02, 0x21, 0x00, 0xE6, 0x4D, 0x15, 0xD7, 0x86, 0xA8,
+        0x38, 0x47, 0x09, 0xEA, 0x2A, 0xD8, 0xAB, 0xE7, 0x07, 0x2F, 0xD7, 0x8E, 0x99, 0x88, 0xF4,
+        0x09, 0xA3, 0xE5, 0x42, 0xD0, 0xFD, 0x8C, 0x9D, 0x30, 0xF9, 0x5C,
+    ];
+
+    #[test]
+    fn ecdsa_sig_value() {
+        let signature = EcdsaSigValue::deserialize(&mut &ECDSA_SIGNATURE[..]).unwrap();
+
+        let r = signature.r_bytes::<32>().unwrap();
+        let s = signature.s_bytes::<32>().unwrap();
+        assert_eq!(r, ECDSA_SIGNATURE[4..36]);
+        assert_eq!(s, ECDSA_SIGNATURE[39..]);
+        assert_eq!(signature.r_bytes::<31>(), None);
+
+        assert_eq!(EcdsaSigValue::from_scalars(&r, &s), signature);
+        assert_eq!(signature.to_der_vec(), ECDSA_SIGNATURE);
+
+        // s with a redundant leading zero
+        let mut non_minimal = ECDSA_SIGNATURE.to_vec();
+        non_minimal[1] += 1;
+        non_minimal[37] += 1;
+        non_minimal.insert(38, 0x00);
+        assert!(EcdsaSigValue::deserialize(&mut &non_minimal[..]).is_err());
+    }
+
+    // A 2048-bit key generated with `openssl rsa -RSAPublicKey_out`
+    const RSA_PUBLIC_KEY: &str = "\
+-----BEGIN RSA PUBLIC KEY-----
+MIIBCgKCAQEAsNWFcvzGh6ud7Dg6P08x8EUDMNtV38wju0gFpouYbL9rNrYmuY6q
+ChTRUliyaBniMR6lKn3SlwxWD08lZEVllZ9xik6mHd1xiSfdI1kkAd2ZOcl5HtlJ
++9qriANuK47rTpP13YKaY+M1P5sKUU1MT0EWxpiLqW1GPAwNvc/GbpyqPqAQekZA
+rPcsP5tpCZwMWHS4OHX/1MT9Ysz4eqAfKscVGaCvI7m12kYOox5+f8g/cprRJIBN
+Xg4aevKRxg1tAESjgBrzZVTM2c9c028nBLdxQXD4qJSM9LTk+Wo+UhbRKXa47S1W
+7tOI9qVygxsluHAzas2lmMm5GO+YkvARwQIDAQAB
+-----END RSA PUBLIC KEY-----
+";
+
+    #[test]
+    fn rsa_public_key() {
+        let (label, der) = crate::pem::from_pem(RSA_PUBLIC_KEY).unwrap();
+        assert_eq!(label, "RSA PUBLIC KEY");
+        assert_eq!(der.len(), 270);
+
+        let key = RsaPublicKey::deserialize(&mut &der[..]).unwrap();
+        assert_eq!(key.bits(), Some(2048));
+        assert_eq!(
+            key.public_exponent.to_unsigned_bytes_be(),
+            Some(&[0x01, 0x00, 0x01][..])
+        );
+
+        // The modulus has its top bit set, so it carries a sign pad
+        assert_eq!(key.modulus.as_signed_bytes_be().len(), 257);
+        let modulus = key.modulus_bytes::<256>().unwrap();
+        assert_eq!(modulus[..4], [0xB0, 0xD5, 0x85, 0x72]);
+        assert!(key.modulus_bytes::<255>().is_none());
+
+        let rebuilt = RsaPublicKey::from_components(&modulus, &[0x01, 0x00, 0x01]);
+        assert_eq!(rebuilt, key);
+        assert_eq!(rebuilt.to_der_vec(), der);
+
+        let small = RsaPublicKey::from_components(&[0x0F, 0xFF], &[0x03]);
+        assert_eq!(small.bits(), Some(12));
+        let negative = RsaPublicKey {
+            modulus: BigInt::from_signed_bytes_be(&[0x80]),
+            public_exponent: BigInt::from_unsigned_bytes_be(&[0x03]),
+        };
+        assert_eq!(negative.bits(), None);
+
+        // A modulus with a redundant leading zero
+        let buffer = [0x30, 0x07, 0x02, 0x02, 0x00, 0x05, 0x02, 0x01, 0x03];
+        assert!(matches!(
+            RsaPublicKey::deserialize(&mut &buffer[..])
+                .unwrap_err()
+                .root_cause(),
+            DerError::InvalidEncoding
+        ));
+    }
+
+    // A self-signed P-256 certificate with keyUsage and subjectAltName
+    // extensions
+    const CERTIFICATE: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIB5DCCAYmgAwIBAgIUM5a3faR3Qz9E28EPAIk7k6b+ef8wCgYIKoZIzj0EAwIw
+MzELMAkGA1UEBhMCVVMxDzANBgNVBAoMBlNlcmRlcjETMBEGA1UEAwwKVmFsdWUg
+VGVzdDAeFw0yNjEwMTQxMDE2NTFaFw0zNjEwMTExMDE2NTFaMDMxCzAJBgNVBAYT
+AlVTMQ8wDQYDVQQKDAZTZXJkZXIxEzARBgNVBAMMClZhbHVlIFRlc3QwWTATBgcq
+hkjOPQIBBggqhkjOPQMBBwNCAAQYeGc7i0fINIrOiLZPt9wYXncdKXfG1YBr6hdM
+xz+1YbGfmZIvNlbpBHev3CxH32LyXFf6i4ZuKdT6cfBBYCAxo3sweTAdBgNVHQ4E
+FgQUjiaECKhOMRigO0QjEA3RgMjHoBcwHwYDVR0jBBgwFoAUjiaECKhOMRigO0Qj
+EA3RgMjHoBcwDwYDVR0TAQH/BAUwAwEB/zAOBgNVHQ8BAf8EBAMCAoQwFgYDVR0R
+BA8wDYILZXhhbXBsZS5jb20wCgYIKoZIzj0EAwIDSQAwRgIhAOkwJZjkxNtJ5vYM
+1I65b/hCAZ181yEjK3k4xYBVbgFiAiEA1CKo4L/rI/FftXVsU+p0I3caz+rj2o++
+BbAcmYbFrb4=
+-----END CERTIFICATE-----
+";
+
+    #[derive(Debug, DerSerialize, DerDeserialize)]
+    struct SignedCertificate {
+        tbs_certificate: crate::Captured<Vec<AnyTlv>>,
+        signature_algorithm: AnyTlv,
+        signature_value: crate::BitString,
+    }
+
+    #[test]
+    fn captured_tbs_certificate() {
+        use std::{
+            collections::hash_map::DefaultHasher,
+            hash::{Hash, Hasher},
+        };
+
+        let (_, der) = crate::pem::from_pem(CERTIFICATE).unwrap();
+        let certificate = SignedCertificate::deserialize(&mut &der[..]).unwrap();
+
+        // The TBSCertificate is the 393 byte SEQUENCE at offset 4
+        let tbs = certificate.tbs_certificate.as_bytes();
+        assert_eq!(tbs.len(), 4 + 393);
+        assert_eq!(tbs, &der[4..4 + 397]);
+        assert_eq!(certificate.tbs_certificate.value().len(), 8);
+
+        // Any hasher sees the same bytes as one run over the original range
+        let digest = |bytes: &[u8]| {
+            let mut hasher = DefaultHasher::new();
+            bytes.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(digest(tbs), digest(&der[4..401]));
+
+        assert_eq!(certificate.signature_value.len(), 72 * 8);
+        assert_eq!(certificate.to_der_vec(), der);
+    }
+
+    #[test]
+    fn typed_certificate() {
+        let (_, der) = crate::pem::from_pem(CERTIFICATE).unwrap();
+        let certificate = Certificate::deserialize(&mut &der[..]).unwrap();
+        let tbs = certificate.tbs_certificate.value();
+
+        assert_eq!(tbs.version, Explicit(Version::V3));
+        assert_eq!(tbs.subject.common_name().as_deref(), Some("Value Test"));
+        assert_eq!(tbs.issuer, tbs.subject);
+        assert!(matches!(tbs.validity.not_before, Time::Utc(_)));
+        assert_eq!(
+            tbs.subject_public_key_info.algorithm.algorithm.as_bytes(),
+            crate::oids::EC_PUBLIC_KEY
+        );
+        assert_eq!(tbs.extensions.as_ref().unwrap().0 .0.get().len(), 5);
+        assert_eq!(
+            certificate.signature_algorithm.algorithm.as_bytes(),
+            crate::oids::ECDSA_WITH_SHA256
+        );
+        assert!(certificate
+            .signature_value
+            .inner_der::<EcdsaSigValue>()
+            .is_ok());
+
+        // Re-encoding the typed TBSCertificate gives back the signed bytes
+        assert_eq!(certificate.tbs_certificate.as_bytes(), &der[4..401]);
+        assert_eq!(tbs.to_der_vec(), &der[4..401]);
+        assert_eq!(certificate.to_der_vec(), der);
+    }
+
+    #[test]
+    fn unique_identifiers() {
+        let (_, der) = crate::pem::from_pem(CERTIFICATE).unwrap();
+        let certificate = Certificate::deserialize(&mut &der[..]).unwrap();
+        let mut tbs = certificate.tbs_certificate.value().clone();
+        assert_eq!(tbs.issuer_unique_id, None);
+        assert_eq!(tbs.subject_unique_id, None);
+
+        tbs.issuer_unique_id = Some(UniqueIdentifier(BitString::new(vec![0xA0], 4).unwrap()));
+        tbs.subject_unique_id = Some(UniqueIdentifier(BitString::from_bytes(vec![0x01, 0x02])));
+        let buffer = tbs.to_der_vec();
+        assert_eq!(tbs.encoded_len(), buffer.len());
+
+        // Between the SubjectPublicKeyInfo and the extensions
+        let spki = tbs.subject_public_key_info.to_der_vec();
+        let at = buffer
+            .windows(spki.len())
+            .position(|window| window == spki)
+            .unwrap()
+            + spki.len();
+        assert_eq!(
+            buffer[at..at + 9],
+            [0x81, 0x02, 0x04, 0xA0, 0x82, 0x03, 0x00, 0x01, 0x02]
+        );
+        assert_eq!(buffer[at + 9], crate::explicit_tag(3));
+
+        let decoded = TbsCertificate::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(decoded, tbs);
+
+        // Only the subject's
+        tbs.issuer_unique_id = None;
+        let buffer = tbs.to_der_vec();
+        assert_eq!(TbsCertificate::deserialize(&mut &buffer[..]).unwrap(), tbs);
+    }
+
+    #[test]
+    fn attribute_set() {
+        use crate::{oid, PRINTABLE_STRING, UTF8_STRING};
+
+        // PKCS #9 contentType and challengePassword
+        let content_type = ObjectIdentifier::from_bytes(oid!(1, 2, 840, 113_549, 1, 9, 3)).unwrap();
+        let challenge = ObjectIdentifier::from_bytes(oid!(1, 2, 840, 113_549, 1, 9, 7)).unwrap();
+        let id_data = ObjectIdentifier::from_bytes(oid!(1, 2, 840, 113_549, 1, 7, 1)).unwrap();
+
+        let attributes = AttributeSet::new(vec![
+            Attribute::new(
+                content_type.clone(),
+                vec![AnyTlv::new(crate::OBJECT_IDENTIFIER, id_data.as_bytes())],
+            ),
+            Attribute::new(
+                challenge.clone(),
+                vec![
+                    AnyTlv::new(PRINTABLE_STRING, b"ab"),
+                    AnyTlv::new(UTF8_STRING, b"pw"),
+                ],
+            ),
+        ]);
+
+        // The shorter challengePassword attribute sorts first, and within it
+        // the UTF8String before the PrintableString
+        let order: Vec<_> = attributes.0.iter().map(|a| &a.attr_type).collect();
+        assert_eq!(order, [&challenge, &content_type]);
+        let values = &attributes.get(&challenge).unwrap().values;
+        assert_eq!(values.as_slice()[0].tag(), UTF8_STRING);
+
+        let buffer = attributes.to_der_vec();
+        assert_eq!(buffer[..2], [0x31, 0x31]);
+        assert_eq!(
+            AttributeSet::deserialize(&mut &buffer[..]).unwrap(),
+            attributes
+        );
+
+        let missing = ObjectIdentifier::from_bytes(oid!(1, 2, 840, 113_549, 1, 9, 4)).unwrap();
+        assert!(attributes.get(&missing).is_none());
+
+        // The same attributes in the order given
+        let unsorted = {
+            let mut attributes = attributes.0.into_inner();
+            attributes.reverse();
+            let content_len: usize = attributes.iter().map(DerSerialize::encoded_len).sum();
+            let mut buffer = vec![0x31, content_len as u8];
+            for attribute in &attributes {
+                attribute.serialize(&mut buffer).unwrap();
+            }
+            buffer
+        };
+        assert!(matches!(
+            AttributeSet::deserialize(&mut &unsorted[..]),
+            Err(DerError::InvalidEncoding)
+        ));
+    }
+
+    #[test]
+    fn certificate_builder() {
+        use crate::{oid, oids, read_contents, INTEGER};
+
+        let name = |cn: &str| {
+            let cn = AnyTlv::new(crate::UTF8_STRING, cn.as_bytes());
+            let attribute = AttributeTypeAndValue {
+                attribute_type: ObjectIdentifier::from_bytes(COMMON_NAME).unwrap(),
+                value: cn,
+            };
+
+            RdnSequence(vec![SetOf::new(vec![attribute])])
+        };
+        let ecdsa_with_sha256 = AlgorithmIdentifier {
+            algorithm: ObjectIdentifier::from_bytes(oids::ECDSA_WITH_SHA256).unwrap(),
+            parameters: None,
+        };
+        let info = SubjectPublicKeyInfo {
+            algorithm: AlgorithmIdentifier {
+                algorithm: ObjectIdentifier::from_bytes(oids::EC_PUBLIC_KEY).unwrap(),
+                parameters: Some(AnyTlv::new(crate::OBJECT_IDENTIFIER, oids::SECP256R1)),
+            },
+            subject_public_key: BitString::from_bytes(vec![0x04; 65]),
+        };
+        let not_before = Time::new(2026, 1, 1, 0, 0, 0).unwrap();
+        let not_after = Time::new(2056, 1, 1, 0, 0, 0).unwrap();
+
+        let builder = CertificateBuilder::new()
+            .serial_number(BigInt::from_unsigned_bytes_be(&[0x01, 0x02]))
+            .signature_algorithm(ecdsa_with_sha256.clone())
+            .issuer(name("Builder CA"))
+            .validity(not_before, not_after)
+            .subject(name("Builder Leaf"))
+            .subject_public_key_info(info.clone());
+
+        // Without extensions it's a v1 certificate, which leaves out the version
+        let buffer = builder.clone().build().unwrap();
+        let contents = read_contents(&mut &buffer[..], crate::SEQUENCE).unwrap();
+        assert_eq!(contents[0], INTEGER);
+
+        let basic_constraints = Extension {
+            extn_id: ObjectIdentifier::from_bytes(oid!(2, 5, 29, 19)).unwrap(),
+            critical: true,
+            extn_value: OctetString::new(vec![0x30, 0x00]),
+        };
+        let builder_v1 = builder.clone();
+        let builder = builder.extension(basic_constraints.clone());
+        let buffer = builder.clone().build().unwrap();
+
+        let mut contents = read_contents(&mut &buffer[..], crate::SEQUENCE).unwrap();
+        assert_eq!(
+            Explicit::<0, Version>::deserialize(&mut contents).unwrap(),
+            Explicit(Version::V3)
+        );
+        assert_eq!(
+            BigInt::deserialize(&mut contents).unwrap(),
+            BigInt::from_unsigned_bytes_be(&[0x01, 0x02])
+        );
+        assert_eq!(
+            AlgorithmIdentifier::deserialize(&mut contents).unwrap(),
+            ecdsa_with_sha256
+        );
+        let issuer = RdnSequence::deserialize(&mut contents).unwrap();
+        assert_eq!(issuer.common_name().as_deref(), Some("Builder CA"));
+        let validity = Validity::deserialize(&mut contents).unwrap();
+        assert_eq!(validity.not_before, not_before);
+        assert!(matches!(validity.not_after, Time::Generalized(_)));
+        let subject = RdnSequence::deserialize(&mut contents).unwrap();
+        assert_eq!(subject.common_name().as_deref(), Some("Builder Leaf"));
+        assert_eq!(
+            SubjectPublicKeyInfo::deserialize(&mut contents).unwrap(),
+            info
+        );
+        assert_eq!(
+            Explicit::<3, Extensions>::deserialize(&mut contents).unwrap(),
+            Explicit(Extensions::new(vec![basic_constraints]).unwrap())
+        );
+        assert!(contents.is_empty());
+
+        let tbs = builder.build_tbs().unwrap();
+        assert_eq!(tbs.version, Explicit(Version::V3));
+        assert_eq!(tbs.to_der_vec(), buffer);
+        assert_eq!(TbsCertificate::deserialize(&mut &buffer[..]).unwrap(), tbs);
+
+        let certificate = Certificate {
+            tbs_certificate: Captured::new(tbs),
+            signature_algorithm: ecdsa_with_sha256,
+            signature_value: BitString::from_bytes(
+                EcdsaSigValue::from_scalars(&[0x11; 32], &[0x22; 32]).to_der_vec(),
+            ),
+        };
+        let buffer = certificate.to_der_vec();
+        assert_eq!(
+            Certificate::deserialize(&mut &buffer[..]).unwrap(),
+            certificate
+        );
+
+        // v1 may only be left out, not encoded as the DEFAULT it is
+        let tbs = builder_v1.build_tbs().unwrap();
+        assert_eq!(tbs.version, Explicit(Version::V1));
+        let buffer = tbs.to_der_vec();
+        assert_eq!(TbsCertificate::deserialize(&mut &buffer[..]).unwrap(), tbs);
+
+        let contents = read_contents(&mut &buffer[..], crate::SEQUENCE).unwrap();
+        let mut explicit_v1 = Vec::new();
+        crate::write_length_prefixed(crate::SEQUENCE, &mut explicit_v1, |tbs| {
+            tbs.extend_from_slice(&[0xA0, 0x03, 0x02, 0x01, 0x00]);
+            tbs.extend_from_slice(contents);
+            Ok(())
+        })
+        .unwrap();
+        let e = TbsCertificate::deserialize(&mut &explicit_v1[..]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "while decoding TbsCertificate.version: invalid DER encoding"
+        );
+        assert!(matches!(e.root_cause(), DerError::InvalidEncoding));
+
+        let e = CertificateBuilder::new()
+            .serial_number(BigInt::from_unsigned_bytes_be(&[1]))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "while building TBSCertificate.signature: unexpected end of input"
+        );
+    }
+}
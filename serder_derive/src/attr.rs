@@ -0,0 +1,316 @@
+use syn::{
+    spanned::Spanned, Attribute, Data, DeriveInput, Fields, GenericArgument, Ident, Member,
+    PathArguments, Type,
+};
+
+pub struct Container {
+    /// Encode as a SET rather than a SEQUENCE
+    pub set: bool,
+    /// Encode exactly as the single field of the struct
+    pub transparent: bool,
+    /// Encode a struct of `bool`s as a BIT STRING named bit list, with
+    /// field order giving the bit number
+    pub bit_flags: bool,
+    /// Encode a fieldless enum as an INTEGER, each variant its discriminant
+    pub integer: bool,
+}
+
+impl Container {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container = Container {
+            set: false,
+            transparent: false,
+            bit_flags: false,
+            integer: false,
+        };
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("der")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("set") {
+                    container.set = true;
+                    Ok(())
+                } else if meta.path.is_ident("transparent") {
+                    container.transparent = true;
+                    Ok(())
+                } else if meta.path.is_ident("bit_flags") {
+                    container.bit_flags = true;
+                    Ok(())
+                } else if meta.path.is_ident("integer") {
+                    container.integer = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown der container attribute"))
+                }
+            })?;
+        }
+
+        Ok(container)
+    }
+
+    pub fn validate_choice(&self, input: &DeriveInput) -> syn::Result<()> {
+        if self.set || self.transparent || self.bit_flags {
+            return Err(syn::Error::new(
+                input.span(),
+                "container attributes aren't supported on enums",
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn validate(&self, input: &DeriveInput, fields: &[Field<'_>]) -> syn::Result<()> {
+        if self.integer {
+            return Err(syn::Error::new(
+                input.span(),
+                "#[der(integer)] is only supported on enums",
+            ));
+        }
+
+        if [self.set, self.transparent, self.bit_flags]
+            .iter()
+            .filter(|attr| **attr)
+            .count()
+            > 1
+        {
+            return Err(syn::Error::new(
+                input.span(),
+                "only one of #[der(set)], #[der(transparent)] and #[der(bit_flags)] may be used",
+            ));
+        }
+
+        match fields.iter().filter(|field| field.unknown).count() {
+            0 => {}
+            1 if self.set => {}
+            1 => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "#[der(unknown)] fields are only supported with #[der(set)]",
+                ))
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "only one field may be #[der(unknown)]",
+                ))
+            }
+        }
+
+        if self.transparent && fields.len() != 1 {
+            return Err(syn::Error::new(
+                input.span(),
+                "#[der(transparent)] requires a struct with exactly one field",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Field<'a> {
+    pub member: Member,
+    pub ty: &'a Type,
+    /// ASN.1 DEFAULT with the `Default` value of the field's type: omitted
+    /// when equal to it, which DER requires
+    pub default: bool,
+    /// Position in the encoding, when it differs from declaration order
+    pub order: Option<usize>,
+    /// A `Vec<AnyTlv>` collecting the SET components no other field matches
+    pub unknown: bool,
+}
+
+impl<'a> Field<'a> {
+    /// The `T` in a field of type `Option<T>`, used to let OPTIONAL
+    /// components be absent.
+    pub fn option_inner(&self) -> Option<&'a Type> {
+        let path = match self.ty {
+            Type::Path(path) if path.qself.is_none() => &path.path,
+            _ => return None,
+        };
+
+        let last = path.segments.last()?;
+
+        if last.ident != "Option" {
+            return None;
+        }
+
+        match &last.arguments {
+            PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+pub fn struct_fields(input: &DeriveInput) -> syn::Result<Vec<Field<'_>>> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "DER derives are only supported on structs and enums",
+            ))
+        }
+    };
+
+    let members = match fields {
+        Fields::Named(_) | Fields::Unnamed(_) => fields.members(),
+        Fields::Unit => return Ok(Vec::new()),
+    };
+
+    let mut parsed_fields = members
+        .zip(fields.iter())
+        .map(|(member, field)| {
+            let mut parsed = Field {
+                member,
+                ty: &field.ty,
+                default: false,
+                order: None,
+                unknown: false,
+            };
+
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("der"))
+            {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("default") {
+                        parsed.default = true;
+                        Ok(())
+                    } else if meta.path.is_ident("unknown") {
+                        parsed.unknown = true;
+                        Ok(())
+                    } else if meta.path.is_ident("order") {
+                        let order: syn::LitInt = meta.value()?.parse()?;
+                        parsed.order = Some(order.base10_parse()?);
+                        Ok(())
+                    } else {
+                        Err(meta.error("unknown der field attribute"))
+                    }
+                })?;
+            }
+
+            Ok(parsed)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // Returned in encoding order, so both derives follow `#[der(order = N)]`
+    let ordered = parsed_fields.iter().filter(|field| field.order.is_some());
+
+    match ordered.count() {
+        0 => {}
+        count if count == parsed_fields.len() => {
+            parsed_fields.sort_by_key(|field| field.order);
+
+            if parsed_fields
+                .windows(2)
+                .any(|pair| pair[0].order == pair[1].order)
+            {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "#[der(order = N)] values must be distinct",
+                ));
+            }
+        }
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "#[der(order = N)] must be given on every field or none",
+            ))
+        }
+    }
+
+    Ok(parsed_fields)
+}
+
+/// The variants of a `#[der(integer)]` enum, which must all be unit
+/// variants.
+pub fn integer_variants(input: &DeriveInput) -> syn::Result<Vec<&Ident>> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "#[der(integer)] is only supported on enums",
+            ))
+        }
+    };
+
+    data.variants
+        .iter()
+        .map(|variant| match variant.fields {
+            Fields::Unit => Ok(&variant.ident),
+            _ => Err(syn::Error::new(
+                variant.span(),
+                "#[der(integer)] variants can't have fields",
+            )),
+        })
+        .collect()
+}
+
+/// An alternative of a CHOICE enum, a variant with a single unnamed field.
+pub struct Variant<'a> {
+    pub ident: &'a Ident,
+    pub ty: &'a Type,
+    /// Catches any element no other variant matches, keeping the CHOICE open
+    /// to alternatives added later
+    pub unknown: bool,
+}
+
+/// The variants of an enum, or `None` if `input` isn't one.
+pub fn choice_variants(input: &DeriveInput) -> syn::Result<Option<Vec<Variant<'_>>>> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => return Ok(None),
+    };
+
+    let variants = data
+        .variants
+        .iter()
+        .map(|variant| {
+            let ty = match &variant.fields {
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+                _ => {
+                    return Err(syn::Error::new(
+                        variant.span(),
+                        "CHOICE variants must have exactly one unnamed field",
+                    ))
+                }
+            };
+
+            let mut parsed = Variant {
+                ident: &variant.ident,
+                ty,
+                unknown: false,
+            };
+
+            for attr in variant
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("der"))
+            {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("unknown") {
+                        parsed.unknown = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("unknown der variant attribute"))
+                    }
+                })?;
+            }
+
+            Ok(parsed)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    if variants.iter().filter(|variant| variant.unknown).count() > 1 {
+        return Err(syn::Error::new(
+            input.span(),
+            "only one variant may be #[der(unknown)]",
+        ));
+    }
+
+    Ok(Some(variants))
+}
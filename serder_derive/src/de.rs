@@ -0,0 +1,299 @@
+use crate::attr::{self, Container, Field, Variant};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, DeriveInput, Ident, Member};
+
+pub fn expand(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    let container = Container::from_attrs(&input.attrs)?;
+
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::serder::DerDeserialize));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Values that aren't a variant's discriminant are rejected
+    if container.integer {
+        container.validate_choice(&input)?;
+        let idents = attr::integer_variants(&input)?;
+
+        return Ok(quote! {
+            impl #impl_generics ::serder::DerDeserialize for #name #ty_generics #where_clause {
+                fn deserialize(__bytes: &mut &[u8]) -> ::std::result::Result<Self, ::serder::DerError> {
+                    let __value = <i128 as ::serder::DerDeserialize>::deserialize(__bytes)?;
+                    #(if __value == Self::#idents as i128 {
+                        return ::std::result::Result::Ok(Self::#idents);
+                    })*
+
+                    ::std::result::Result::Err(::serder::DerError::InvalidEncoding)
+                }
+
+                fn matches_tag(tag: u8) -> bool {
+                    tag == ::serder::INTEGER
+                }
+            }
+        });
+    }
+
+    if let Some(variants) = attr::choice_variants(&input)? {
+        container.validate_choice(&input)?;
+        let (deserialize, matches_tag) = deserialize_choice(name, &variants);
+
+        return Ok(quote! {
+            impl #impl_generics ::serder::DerDeserialize for #name #ty_generics #where_clause {
+                fn deserialize(__bytes: &mut &[u8]) -> ::std::result::Result<Self, ::serder::DerError> {
+                    #deserialize
+                }
+
+                fn matches_tag(tag: u8) -> bool {
+                    #matches_tag
+                }
+            }
+        });
+    }
+
+    let fields = attr::struct_fields(&input)?;
+    container.validate(&input, &fields)?;
+
+    if container.transparent {
+        let member = &fields[0].member;
+        let ty = fields[0].ty;
+
+        return Ok(quote! {
+            impl #impl_generics ::serder::DerDeserialize for #name #ty_generics #where_clause {
+                fn deserialize(__bytes: &mut &[u8]) -> ::std::result::Result<Self, ::serder::DerError> {
+                    ::std::result::Result::Ok(Self {
+                        #member: ::serder::DerDeserialize::deserialize(__bytes)?,
+                    })
+                }
+
+                fn matches_tag(tag: u8) -> bool {
+                    <#ty as ::serder::DerDeserialize>::matches_tag(tag)
+                }
+            }
+        });
+    }
+
+    if container.bit_flags {
+        let members = fields.iter().map(|field| &field.member);
+        let indices = 0..fields.len();
+        let count = fields.len();
+
+        return Ok(quote! {
+            impl #impl_generics ::serder::DerDeserialize for #name #ty_generics #where_clause {
+                fn deserialize(__bytes: &mut &[u8]) -> ::std::result::Result<Self, ::serder::DerError> {
+                    let __bits = <::serder::BitString as ::serder::DerDeserialize>::deserialize(__bytes)?;
+                    ::serder::__private::check_named_bits(&__bits, #count)?;
+
+                    ::std::result::Result::Ok(Self {
+                        #(#members: __bits.get_bit(#indices),)*
+                    })
+                }
+
+                fn matches_tag(tag: u8) -> bool {
+                    tag == ::serder::BIT_STRING
+                }
+            }
+        });
+    }
+
+    let (tag, deserialize) = if container.set {
+        (quote!(::serder::SET), deserialize_set(name, &fields))
+    } else {
+        (
+            quote!(::serder::SEQUENCE),
+            deserialize_sequence(name, &fields),
+        )
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::serder::DerDeserialize for #name #ty_generics #where_clause {
+            fn deserialize(__bytes: &mut &[u8]) -> ::std::result::Result<Self, ::serder::DerError> {
+                let mut __contents = ::serder::read_contents(__bytes, #tag)?;
+                #deserialize
+            }
+
+            fn matches_tag(tag: u8) -> bool {
+                tag == #tag
+            }
+        }
+    })
+}
+
+// Annotates errors from decoding a field with the field's name
+fn field_context(name: &Ident, field: &Field<'_>, decode: TokenStream) -> TokenStream {
+    let context = match &field.member {
+        Member::Named(ident) => format!("while decoding {}.{}", name, ident),
+        Member::Unnamed(index) => format!("while decoding {}.{}", name, index.index),
+    };
+
+    quote!(::serder::context(#decode, || #context))
+}
+
+// DEFAULT components must not be encoded with their default value
+fn component_decode(field: &Field<'_>) -> TokenStream {
+    if field.default {
+        quote! {
+            ::serder::DerDeserialize::deserialize(&mut __contents)
+                .and_then(::serder::__private::reject_default)
+        }
+    } else {
+        quote!(::serder::DerDeserialize::deserialize(&mut __contents))
+    }
+}
+
+fn deserialize_sequence(name: &Ident, fields: &[Field<'_>]) -> TokenStream {
+    let members = fields.iter().map(|field| &field.member);
+    let decodes = fields.iter().map(|field| {
+        let decode = if field.default {
+            quote!(::serder::__private::deserialize_default(&mut __contents))
+        } else {
+            component_decode(field)
+        };
+
+        field_context(name, field, decode)
+    });
+
+    quote! {
+        let __value = Self {
+            #(#members: #decodes?,)*
+        };
+
+        if !__contents.is_empty() {
+            return ::std::result::Result::Err(::serder::DerError::InvalidEncoding);
+        }
+
+        ::std::result::Result::Ok(__value)
+    }
+}
+
+// SET components may appear in any order, so each element is matched to a
+// field by its tag. Elements no field matches go to the `#[der(unknown)]`
+// field if there is one, and are an error otherwise.
+fn deserialize_set(name: &Ident, fields: &[Field<'_>]) -> TokenStream {
+    let unknown = fields.iter().find(|field| field.unknown);
+    let fields: Vec<_> = fields.iter().filter(|field| !field.unknown).collect();
+
+    let slots: Vec<_> = (0..fields.len())
+        .map(|i| format_ident!("__field{}", i))
+        .collect();
+    let tys = fields.iter().map(|field| field.ty);
+    let indices = 0..fields.len();
+    let decodes = fields
+        .iter()
+        .map(|field| field_context(name, field, component_decode(field)));
+
+    let values = fields.iter().zip(&slots).map(|(field, slot)| {
+        let member = &field.member;
+
+        if field.default {
+            quote!(#member: #slot.unwrap_or_default())
+        } else if field.option_inner().is_some() {
+            quote!(#member: #slot.unwrap_or(::std::option::Option::None))
+        } else {
+            let missing = field_context(
+                name,
+                field,
+                quote!(#slot.ok_or(::serder::DerError::UnexpectedEof)),
+            );
+
+            quote!(#member: #missing?)
+        }
+    });
+
+    let matchers = fields.iter().map(|field| {
+        let ty = field.ty;
+        quote!(<#ty as ::serder::DerDeserialize>::matches_tag)
+    });
+
+    let (unknown_slot, unknown_arm, unknown_value) = match unknown {
+        Some(field) => {
+            let member = &field.member;
+            let decode = field_context(
+                name,
+                field,
+                quote!(<::serder::AnyTlv as ::serder::DerDeserialize>::deserialize(
+                    &mut __contents
+                )),
+            );
+
+            (
+                quote!(let mut __unknown: ::std::vec::Vec<::serder::AnyTlv> = ::std::vec::Vec::new();),
+                quote!(::std::option::Option::None => __unknown.push(#decode?),),
+                quote!(#member: __unknown,),
+            )
+        }
+        None => (
+            quote!(),
+            quote! {
+                ::std::option::Option::None => {
+                    return ::std::result::Result::Err(::serder::__private::unexpected_tag(__contents[0]));
+                }
+            },
+            quote!(),
+        ),
+    };
+
+    quote! {
+        #(let mut #slots: ::std::option::Option<#tys> = ::std::option::Option::None;)*
+        #unknown_slot
+        let __matchers: &[fn(u8) -> bool] = &[#(#matchers),*];
+
+        while !__contents.is_empty() {
+            match ::serder::__private::set_component_position(__contents, __matchers)? {
+                #(::std::option::Option::Some(#indices) => {
+                    if #slots.is_some() {
+                        return ::std::result::Result::Err(::serder::DerError::InvalidEncoding);
+                    }
+
+                    #slots = ::std::option::Option::Some(#decodes?);
+                })*
+                #unknown_arm
+                _ => unreachable!(),
+            }
+        }
+
+        ::std::result::Result::Ok(Self {
+            #(#values,)*
+            #unknown_value
+        })
+    }
+}
+
+// A CHOICE decodes as the first alternative whose type accepts the tag,
+// trying the `#[der(unknown)]` variant only once the others have been ruled
+// out
+fn deserialize_choice(name: &Ident, variants: &[Variant<'_>]) -> (TokenStream, TokenStream) {
+    let ordered = variants
+        .iter()
+        .filter(|variant| !variant.unknown)
+        .chain(variants.iter().filter(|variant| variant.unknown));
+
+    let arms = ordered.map(|variant| {
+        let ident = variant.ident;
+        let ty = variant.ty;
+        let context = format!("while decoding {}::{}", name, ident);
+
+        quote! {
+            if <#ty as ::serder::DerDeserialize>::matches_tag(__tag) {
+                return ::serder::context(
+                    ::serder::DerDeserialize::deserialize(__bytes).map(Self::#ident),
+                    || #context,
+                );
+            }
+        }
+    });
+
+    let tys = variants.iter().map(|variant| variant.ty);
+
+    let deserialize = quote! {
+        let __tag = *__bytes.first().ok_or(::serder::DerError::UnexpectedEof)?;
+        #(#arms)*
+
+        ::std::result::Result::Err(::serder::__private::unexpected_tag(__tag))
+    };
+    let matches_tag = quote!(false #(|| <#tys as ::serder::DerDeserialize>::matches_tag(tag))*);
+
+    (deserialize, matches_tag)
+}
@@ -0,0 +1,141 @@
+use crate::attr::{self, Container};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, DeriveInput};
+
+pub fn expand(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    let container = Container::from_attrs(&input.attrs)?;
+
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::serder::DerSerialize));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    if container.integer {
+        container.validate_choice(&input)?;
+        let idents = attr::integer_variants(&input)?;
+
+        return Ok(quote! {
+            impl #impl_generics ::serder::DerSerialize for #name #ty_generics #where_clause {
+                fn serialize<__W: ::std::io::Write + ?::std::marker::Sized>(&self, __writer: &mut __W) -> ::std::io::Result<usize> {
+                    let __value = match self {
+                        #(Self::#idents => Self::#idents as i128,)*
+                    };
+
+                    ::serder::DerSerialize::serialize(&__value, __writer)
+                }
+            }
+        });
+    }
+
+    if let Some(variants) = attr::choice_variants(&input)? {
+        container.validate_choice(&input)?;
+        let idents: Vec<_> = variants.iter().map(|variant| variant.ident).collect();
+
+        return Ok(quote! {
+            impl #impl_generics ::serder::DerSerialize for #name #ty_generics #where_clause {
+                fn serialize<__W: ::std::io::Write + ?::std::marker::Sized>(&self, __writer: &mut __W) -> ::std::io::Result<usize> {
+                    match self {
+                        #(Self::#idents(__value) => ::serder::DerSerialize::serialize(__value, __writer),)*
+                    }
+                }
+
+                fn encoded_len(&self) -> usize {
+                    match self {
+                        #(Self::#idents(__value) => ::serder::DerSerialize::encoded_len(__value),)*
+                    }
+                }
+            }
+        });
+    }
+
+    let fields = attr::struct_fields(&input)?;
+    container.validate(&input, &fields)?;
+    let members: Vec<_> = fields.iter().map(|field| &field.member).collect();
+
+    if container.transparent {
+        let member = members[0];
+
+        return Ok(quote! {
+            impl #impl_generics ::serder::DerSerialize for #name #ty_generics #where_clause {
+                fn serialize<__W: ::std::io::Write + ?::std::marker::Sized>(&self, __writer: &mut __W) -> ::std::io::Result<usize> {
+                    ::serder::DerSerialize::serialize(&self.#member, __writer)
+                }
+
+                fn encoded_len(&self) -> usize {
+                    ::serder::DerSerialize::encoded_len(&self.#member)
+                }
+            }
+        });
+    }
+
+    if container.bit_flags {
+        return Ok(quote! {
+            impl #impl_generics ::serder::DerSerialize for #name #ty_generics #where_clause {
+                fn serialize<__W: ::std::io::Write + ?::std::marker::Sized>(&self, __writer: &mut __W) -> ::std::io::Result<usize> {
+                    let __bits = ::serder::BitString::from_named_bits(&[#(self.#members),*]);
+                    ::serder::DerSerialize::serialize(&__bits, __writer)
+                }
+            }
+        });
+    }
+
+    // DEFAULT components are left out when they hold the default value
+    let values: Vec<_> = fields
+        .iter()
+        .filter(|field| !field.unknown)
+        .map(|field| {
+            let member = &field.member;
+
+            if field.default {
+                quote!(::serder::__private::non_default(&self.#member))
+            } else {
+                quote!(&self.#member)
+            }
+        })
+        .collect();
+
+    // Unknown SET components are written back among the known ones
+    let unknown = fields
+        .iter()
+        .find(|field| field.unknown)
+        .map(|field| &field.member);
+    let unknown_components = unknown.iter().map(|member| {
+        quote!(__components.extend(self.#member.iter().map(::serder::DerSerialize::to_der_vec));)
+    });
+    let unknown_len = unknown.iter().map(|member| {
+        quote!(+ self.#member.iter().map(::serder::DerSerialize::encoded_len).sum::<usize>())
+    });
+
+    let serialize = if container.set {
+        quote! {
+            #[allow(unused_mut)]
+            let mut __components = ::std::vec![#(::serder::DerSerialize::to_der_vec(&#values)),*];
+            #(#unknown_components)*
+
+            ::serder::__private::serialize_set(__components, __writer)
+        }
+    } else {
+        quote! {
+            let __content_len = 0usize #(+ ::serder::DerSerialize::encoded_len(&#values))*;
+            let mut __written = ::serder::write_header(::serder::SEQUENCE, __content_len, __writer)?;
+            #(__written += ::serder::DerSerialize::serialize(&#values, __writer)?;)*
+
+            ::std::result::Result::Ok(__written)
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::serder::DerSerialize for #name #ty_generics #where_clause {
+            fn serialize<__W: ::std::io::Write + ?::std::marker::Sized>(&self, __writer: &mut __W) -> ::std::io::Result<usize> {
+                #serialize
+            }
+
+            fn encoded_len(&self) -> usize {
+                ::serder::tlv_len(0usize #(+ ::serder::DerSerialize::encoded_len(&#values))* #(#unknown_len)*)
+            }
+        }
+    })
+}